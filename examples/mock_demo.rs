@@ -1,11 +1,12 @@
 use zkp_rs::{
     backend::{mock::MockProver, sha256_commit::Sha256Commit},
     core::{Claim, Commit, Prove, Statement, Witness},
+    error::ZkpError,
 };
 
-fn main() {
+fn main() -> Result<(), ZkpError> {
     let statement = Statement {
-        commitment: Sha256Commit.commit("血小板 50, 年龄 42".as_bytes()),
+        commitment: Sha256Commit.commit("血小板 50, 年龄 42".as_bytes())?,
         claim: Claim::Substring {
             value: "年龄 42".into(),
         },
@@ -13,10 +14,12 @@ fn main() {
 
     let witness = Witness {
         plaintext: "血小板 50, 年龄 42".as_bytes().to_vec(),
+        salt: Vec::new(),
     };
 
     let prover = MockProver;
-    let proof = prover.prove(&statement, &witness);
-    assert!(prover.verify(&statement, &proof));
+    let proof = prover.prove(&statement, &witness)?;
+    prover.verify(&statement, &proof)?;
+    Ok(())
 }
 // 运行：cargo run --example mock_demo
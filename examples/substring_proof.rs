@@ -22,7 +22,7 @@ fn main() -> Result<(), ZkpError> {
     let config = CircuitConfig {
         max_text_len: 128,             // 支持更长的原文
         max_substring_len: 32,         // 支持更长的子串
-        enable_multi_block_sha: false, // MVP版本使用单块
+        enable_multi_block_sha: true,  // 原文超过55字节需要多块 SHA-256
     };
 
     println!("📋 电路配置:");
@@ -62,17 +62,17 @@ fn run_hello_world_example(config: &CircuitConfig) -> Result<(), ZkpError> {
     let commitment = hasher.finalize().into();
 
     println!("🌍 公开输入（所有人可见）:");
-    println!("  - 承诺（SHA-256）: {}", hex::encode(&commitment));
+    println!("  - 承诺（SHA-256）: {}", hex::encode(commitment));
     println!("  - 公开子串: {:?}", String::from_utf8_lossy(&substring));
 
     // 创建电路参数
     let params = CircuitParams {
         config: config.clone(),
-        public_inputs: PublicInputs {
-            commitment,
-            substring: substring.clone(),
-        },
-        witness: Some(CircuitWitness { plaintext, offset }),
+        public_inputs: PublicInputs::substring(commitment, substring.clone()),
+        witness: Some(CircuitWitness {
+            plaintext,
+            offsets: vec![offset],
+        }),
     };
 
     // 3. 生成证明（证明者侧）
@@ -102,7 +102,9 @@ fn run_hello_world_example(config: &CircuitConfig) -> Result<(), ZkpError> {
     let air = SubstringAIR::new(config.clone(), layout.clone());
 
     let air_start = Instant::now();
-    let air_valid = air.verify_all_constraints(&trace, &params.public_inputs)?;
+    let air_valid = air
+        .verify_all_constraints(&trace, &params.public_inputs)
+        .is_ok();
     println!(
         "    AIR 约束: {}",
         if air_valid {
@@ -145,16 +147,16 @@ fn run_complex_example(config: &CircuitConfig) -> Result<(), ZkpError> {
     let commitment = hasher.finalize().into();
 
     println!("🌍 公开输入:");
-    println!("  - 承诺: {}", hex::encode(&commitment));
+    println!("  - 承诺: {}", hex::encode(commitment));
     println!("  - 公开子串: {:?}", String::from_utf8_lossy(&substring));
 
     let params = CircuitParams {
         config: config.clone(),
-        public_inputs: PublicInputs {
-            commitment,
-            substring,
-        },
-        witness: Some(CircuitWitness { plaintext, offset }),
+        public_inputs: PublicInputs::substring(commitment, substring),
+        witness: Some(CircuitWitness {
+            plaintext,
+            offsets: vec![offset],
+        }),
     };
 
     let circuit = SubstringCircuit::new(config.clone());
@@ -196,11 +198,11 @@ fn run_failure_example(config: &CircuitConfig) -> Result<(), ZkpError> {
 
     let params = CircuitParams {
         config: config.clone(),
-        public_inputs: PublicInputs {
-            commitment,
-            substring: fake_substring,
-        },
-        witness: Some(CircuitWitness { plaintext, offset }),
+        public_inputs: PublicInputs::substring(commitment, fake_substring),
+        witness: Some(CircuitWitness {
+            plaintext,
+            offsets: vec![offset],
+        }),
     };
 
     let circuit = SubstringCircuit::new(config.clone());
@@ -242,11 +244,11 @@ fn run_benchmark(config: &CircuitConfig) -> Result<(), ZkpError> {
 
         let params = CircuitParams {
             config: config.clone(),
-            public_inputs: PublicInputs {
-                commitment,
-                substring,
-            },
-            witness: Some(CircuitWitness { plaintext, offset }),
+            public_inputs: PublicInputs::substring(commitment, substring),
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![offset],
+            }),
         };
 
         let start = Instant::now();
@@ -0,0 +1,480 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        merkle_commit::{MerkleTree, SALT_LEN, decode_commitment, verify_path},
+        stark_prover::StarkProver,
+    },
+    circuits::occurrence::occurrences,
+    config::{CircuitConfig, MaskedWindow, PublicInputs, WindowInput, WindowOrder, WindowPosition},
+    core::{Claim, ClaimKind, FieldId, Proof, ProofEnvelope, Prove, Statement, Witness},
+    error::ZkpError,
+};
+
+/// Where a piece of the substring sits within its chunk
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum PiecePosition {
+    /// Anywhere inside the chunk
+    Inside,
+    /// At the first byte of the chunk
+    Start,
+    /// At the last byte of the chunk
+    End,
+    /// The whole chunk
+    Whole,
+}
+
+/// Proof that one piece of the substring lies in one chunk of the committed document
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ChunkOpening {
+    /// Chunk index
+    index: usize,
+    /// Number of substring bytes in this chunk
+    length: usize,
+    position: PiecePosition,
+    /// Salted chunk hash, the commitment of the inner proof
+    leaf: [u8; 32],
+    /// Merkle path from the leaf to the root
+    siblings: Vec<[u8; 32]>,
+    /// Inner STARK proof for the chunk
+    proof: Vec<u8>,
+}
+
+/// Proves [`Claim::ChunkedSubstring`] against a
+/// [`MerkleCommit`](super::merkle_commit::MerkleCommit) commitment.
+///
+/// The substring is cut at chunk boundaries and each piece is proven by the inner
+/// [`StarkProver`] against the leaf of its chunk, with a Merkle path from that leaf to the
+/// root. The inner plaintext is the chunk behind its private salt: a fully hidden window pins
+/// the salt to the first [`SALT_LEN`] bytes and the piece is ordered after it, so it can only
+/// match chunk bytes. The circuit only ever hashes a single salted chunk, so proving cost
+/// depends on the chunk size and the substring length but not on the document size. Pieces
+/// after the first are pinned to the start of their chunk and pieces before the last to its
+/// end, so the pieces join up into one occurrence.
+///
+/// An opening reveals the chunk index, the piece length and where the piece sits in its chunk
+/// (inside, at the start, at the end or the whole chunk), so a substring spanning chunks also
+/// reveals where it starts. It also publishes the leaf and the sibling hashes on the path to
+/// the root; every leaf is salted, so these hashes say nothing about the chunks. The inner
+/// proofs only hide the chunk bytes with [`Self::new_hiding`].
+pub struct ChunkedStarkProver {
+    prover: StarkProver,
+    chunk_size: usize,
+}
+
+impl ChunkedStarkProver {
+    /// Create a prover for documents committed with `chunk_size`-byte chunks. The circuit
+    /// configuration must fit one salted chunk, not the whole document, so `chunk_size` must be
+    /// between 1 and `config.max_text_len - SALT_LEN`, and the hidden salt window needs
+    /// `config.max_substring_len >= SALT_LEN`.
+    pub fn new(config: CircuitConfig, chunk_size: usize) -> Result<Self, ZkpError> {
+        check_chunk_size(&config, chunk_size)?;
+        Ok(Self {
+            prover: StarkProver::new(config),
+            chunk_size,
+        })
+    }
+
+    /// Create a zero-knowledge prover, see [`StarkProver::new_hiding`]
+    pub fn new_hiding(config: CircuitConfig, chunk_size: usize) -> Result<Self, ZkpError> {
+        check_chunk_size(&config, chunk_size)?;
+        Ok(Self {
+            prover: StarkProver::new_hiding(config),
+            chunk_size,
+        })
+    }
+}
+
+/// Reject a chunk size the inner circuit cannot hash in one proof
+fn check_chunk_size(config: &CircuitConfig, chunk_size: usize) -> Result<(), ZkpError> {
+    if chunk_size == 0 || chunk_size + SALT_LEN > config.max_text_len {
+        return Err(ZkpError::ConfigurationError(format!(
+            "Chunk size {} plus the {}-byte salt must be between 1 and the maximum plaintext \
+             length {}",
+            chunk_size, SALT_LEN, config.max_text_len
+        )));
+    }
+    if config.max_substring_len < SALT_LEN {
+        return Err(ZkpError::ConfigurationError(format!(
+            "Maximum substring length {} cannot hold the {}-byte salt window",
+            config.max_substring_len, SALT_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// The substring bytes of a chunked claim
+fn chunked_value(statement: &Statement) -> Result<&[u8], ZkpError> {
+    match &statement.claim {
+        Claim::ChunkedSubstring { value } if !value.is_empty() => Ok(value.as_bytes()),
+        Claim::ChunkedSubstring { .. } => Err(ZkpError::InvalidPublicInput(
+            "Chunked substring claim needs a non-empty value".to_string(),
+        )),
+        _ => Err(ZkpError::InvalidPublicInput(
+            "Chunked prover only proves chunked substring claims".to_string(),
+        )),
+    }
+}
+
+/// Public inputs of the inner proof for one piece, proven over `salt || chunk`: window 0 is the
+/// hidden salt at the start of the plaintext and window 1 the piece, which comes after it.
+/// Only the full chunks before the last one have a piece at their end, so an end piece fixes
+/// the plaintext length to `SALT_LEN + chunk_size`.
+fn piece_inputs(
+    leaf: [u8; 32],
+    bytes: &[u8],
+    position: PiecePosition,
+    chunk_size: usize,
+) -> PublicInputs {
+    let mut public_inputs = PublicInputs {
+        windows: vec![
+            WindowInput::new(vec![0; SALT_LEN]),
+            WindowInput::new(bytes.to_vec()),
+        ],
+        masked: vec![MaskedWindow {
+            window: 0,
+            hidden: vec![true; SALT_LEN],
+        }],
+        prefixes: vec![0],
+        orders: vec![WindowOrder {
+            before: 0,
+            after: 1,
+        }],
+        ..PublicInputs::new(leaf)
+    };
+    let at_start = WindowPosition {
+        window: 1,
+        offset: SALT_LEN,
+    };
+    match position {
+        PiecePosition::Inside => {}
+        PiecePosition::Start => public_inputs.positions.push(at_start),
+        PiecePosition::End => {
+            public_inputs.suffixes.push(1);
+            public_inputs.length = Some(SALT_LEN + chunk_size);
+        }
+        PiecePosition::Whole => {
+            public_inputs.positions.push(at_start);
+            public_inputs.length = Some(SALT_LEN + bytes.len());
+        }
+    }
+    public_inputs
+}
+
+impl Prove for ChunkedStarkProver {
+    fn prove(&self, statement: &Statement, witness: &Witness) -> Result<Proof, ZkpError> {
+        let value = chunked_value(statement)?;
+        let plaintext = &witness.plaintext;
+        let tree = MerkleTree::new(plaintext, self.chunk_size, &witness.salt)?;
+        if tree.commitment() != statement.commitment {
+            return Err(ZkpError::InvalidWitness(
+                "Plaintext does not match the Merkle commitment".to_string(),
+            ));
+        }
+        let offset = occurrences(plaintext, value)
+            .next()
+            .ok_or(ZkpError::InvalidWitness("Substring not found".to_string()))?;
+
+        let mut openings = Vec::new();
+        let mut start = offset;
+        let end = offset + value.len();
+        while start < end {
+            let index = start / self.chunk_size;
+            let chunk = tree.chunk_range(index, plaintext.len());
+            let piece_end = end.min(chunk.end);
+            // Only the joins are pinned: a piece after the first starts its chunk and a piece
+            // before the last ends it, which also makes it a full chunk
+            let position = match (start > offset, piece_end < end) {
+                (false, false) => PiecePosition::Inside,
+                (true, false) => PiecePosition::Start,
+                (false, true) => PiecePosition::End,
+                (true, true) => PiecePosition::Whole,
+            };
+
+            let leaf = tree.leaf(index);
+            let public_inputs = piece_inputs(
+                leaf,
+                &plaintext[start .. piece_end],
+                position,
+                self.chunk_size,
+            );
+            let chunk_witness = Witness {
+                plaintext: [&tree.salt(index)[..], &plaintext[chunk.clone()]].concat(),
+                salt: Vec::new(),
+            };
+            let proof = self.prover.prove_inputs(
+                statement,
+                public_inputs,
+                &chunk_witness,
+                Some(vec![0, SALT_LEN + start - chunk.start]),
+            )?;
+
+            openings.push(ChunkOpening {
+                index,
+                length: piece_end - start,
+                position,
+                leaf,
+                siblings: tree.path(index),
+                proof: proof.inner,
+            });
+            start = piece_end;
+        }
+
+        let payload = bincode::serde::encode_to_vec(&openings, bincode::config::standard())
+            .map_err(|e| ZkpError::SerializationError(format!("Serialization failed: {}", e)))?;
+        let envelope = ProofEnvelope::new(
+            self.prover.backend_id(),
+            FieldId::Goldilocks,
+            ClaimKind::from(&statement.claim),
+            self.prover.config_hash()?,
+            payload,
+        );
+        Ok(Proof {
+            inner: envelope.to_bytes()?,
+        })
+    }
+
+    fn verify(&self, statement: &Statement, proof: &Proof) -> Result<(), ZkpError> {
+        let value = chunked_value(statement)?;
+        let (root, chunks) = decode_commitment(&statement.commitment)?;
+
+        let envelope = ProofEnvelope::from_bytes(&proof.inner)?;
+        envelope.ensure_matches(
+            self.prover.backend_id(),
+            FieldId::Goldilocks,
+            ClaimKind::from(&statement.claim),
+            &self.prover.config_hash()?,
+        )?;
+        let (openings, _): (Vec<ChunkOpening>, usize) =
+            bincode::serde::decode_from_slice(&envelope.payload, bincode::config::standard())
+                .map_err(|e| {
+                    ZkpError::SerializationError(format!("Deserialization failed: {}", e))
+                })?;
+
+        // The pieces cover the substring exactly, in consecutive chunks
+        let rejected = |reason: &str| Err(ZkpError::ProofVerificationFailed(reason.to_string()));
+        if openings.iter().any(|opening| opening.length == 0)
+            || openings
+                .iter()
+                .try_fold(0usize, |sum, opening| sum.checked_add(opening.length))
+                != Some(value.len())
+        {
+            return rejected("Chunk pieces do not cover the substring");
+        }
+        let first = openings[0].index;
+        if openings
+            .iter()
+            .enumerate()
+            .any(|(k, opening)| first.checked_add(k) != Some(opening.index))
+        {
+            return rejected("Chunk pieces are not in consecutive chunks");
+        }
+
+        // Every piece but the last runs to the end of its chunk, every piece but the first
+        // starts at the beginning of its chunk
+        let last = openings.len() - 1;
+        for (k, opening) in openings.iter().enumerate() {
+            let allowed = match (k == 0, k == last) {
+                (true, true) => true,
+                (true, false) => {
+                    matches!(opening.position, PiecePosition::End | PiecePosition::Whole)
+                }
+                (false, true) => matches!(
+                    opening.position,
+                    PiecePosition::Start | PiecePosition::Whole
+                ),
+                (false, false) => opening.position == PiecePosition::Whole,
+            };
+            if !allowed {
+                return rejected("Chunk pieces do not join up at chunk boundaries");
+            }
+        }
+
+        let mut start = 0;
+        for opening in &openings {
+            if !verify_path(
+                &root,
+                chunks,
+                opening.index,
+                &opening.leaf,
+                &opening.siblings,
+            ) {
+                return rejected("Merkle path does not lead to the committed root");
+            }
+            let bytes = &value[start .. start + opening.length];
+            start += opening.length;
+            let public_inputs =
+                piece_inputs(opening.leaf, bytes, opening.position, self.chunk_size);
+            self.prover.verify_inputs(
+                statement,
+                &public_inputs,
+                &Proof {
+                    inner: opening.proof.clone(),
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::*;
+    use crate::{backend::merkle_commit::MerkleCommit, core::Commit, hash::sha256::hash};
+
+    #[test]
+    fn test_chunked_substring_claim() {
+        let config = CircuitConfig {
+            max_text_len: 48,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+        let prover = ChunkedStarkProver::new(config.clone(), 32).unwrap();
+
+        // A salted chunk must be non-empty and fit the circuit, and the salt must fit a window
+        for chunk_size in [0, 33] {
+            assert!(matches!(
+                ChunkedStarkProver::new(config.clone(), chunk_size),
+                Err(ZkpError::ConfigurationError(_))
+            ));
+        }
+        let narrow = CircuitConfig {
+            max_substring_len: SALT_LEN - 1,
+            ..config.clone()
+        };
+        assert!(matches!(
+            ChunkedStarkProver::new(narrow, 32),
+            Err(ZkpError::ConfigurationError(_))
+        ));
+
+        // 8 chunks: the cost of a proof depends on the chunks it opens, not on the document
+        let plaintext: Vec<u8> = (0 .. 8)
+            .flat_map(|i| format!("chunk {} of the logs: status=ok;\n", i).into_bytes())
+            .collect();
+        assert_eq!(plaintext.len(), 8 * 32);
+        let seed = b"fresh secret seed for this log".to_vec();
+        let commitment = MerkleCommit::new(32, seed.clone())
+            .commit(&plaintext)
+            .unwrap();
+        let statement = |value: &[u8]| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::ChunkedSubstring {
+                value: String::from_utf8(value.to_vec()).unwrap(),
+            },
+        };
+        let witness = Witness {
+            plaintext: plaintext.clone(),
+            salt: seed.clone(),
+        };
+
+        // Inside one chunk, at the end of the document, and across the boundary between chunks
+        // 2 and 3
+        for value in [
+            &plaintext[70 .. 80],
+            &plaintext[250 .. 256],
+            &plaintext[90 .. 100],
+        ] {
+            let proof = prover.prove(&statement(value), &witness).unwrap();
+            assert!(prover.verify(&statement(value), &proof).is_ok());
+            assert!(prover.verify(&statement(b"status=ok"), &proof).is_err());
+        }
+
+        // Openings built by hand: pieces of chunks 2 and 5 do not join up into one substring,
+        // whether the chunk index is reported honestly or not, even though the spliced value
+        // happens to occur across chunks 4 and 5
+        let tree = MerkleTree::new(&plaintext, 32, &seed).unwrap();
+        let open = |statement: &Statement, index: usize, claimed: usize, bytes: Range<usize>| {
+            let chunk = tree.chunk_range(index, plaintext.len());
+            let position = if bytes.start == chunk.start {
+                PiecePosition::Start
+            } else {
+                PiecePosition::End
+            };
+            let proof = prover
+                .prover
+                .prove_inputs(
+                    statement,
+                    piece_inputs(tree.leaf(index), &plaintext[bytes.clone()], position, 32),
+                    &Witness {
+                        plaintext: [&tree.salt(index)[..], &plaintext[chunk.clone()]].concat(),
+                        salt: Vec::new(),
+                    },
+                    Some(vec![0, SALT_LEN + bytes.start - chunk.start]),
+                )
+                .unwrap();
+            ChunkOpening {
+                index: claimed,
+                length: bytes.len(),
+                position,
+                leaf: tree.leaf(index),
+                siblings: tree.path(index),
+                proof: proof.inner,
+            }
+        };
+        let forge = |openings: Vec<ChunkOpening>| Proof {
+            inner: ProofEnvelope::new(
+                prover.prover.backend_id(),
+                FieldId::Goldilocks,
+                ClaimKind::ChunkedSubstring,
+                prover.prover.config_hash().unwrap(),
+                bincode::serde::encode_to_vec(&openings, bincode::config::standard()).unwrap(),
+            )
+            .to_bytes()
+            .unwrap(),
+        };
+
+        let joined = statement(&plaintext[90 .. 103]);
+        let proof = forge(vec![
+            open(&joined, 2, 2, 90 .. 96),
+            open(&joined, 3, 3, 96 .. 103),
+        ]);
+        assert!(prover.verify(&joined, &proof).is_ok());
+
+        let spliced = [&plaintext[90 .. 96], &plaintext[160 .. 167]].concat();
+        let spliced = statement(&spliced);
+        for claimed in [5, 3] {
+            let proof = forge(vec![
+                open(&spliced, 2, 2, 90 .. 96),
+                open(&spliced, 5, claimed, 160 .. 167),
+            ]);
+            assert!(matches!(
+                prover.verify(&spliced, &proof),
+                Err(ZkpError::ProofVerificationFailed(_))
+            ));
+        }
+
+        // A proof only holds for the committed document
+        let proof = prover.prove(&statement(b"status=ok"), &witness).unwrap();
+        let mut other = plaintext.clone();
+        other[255] ^= 1;
+        let other_statement = Statement {
+            commitment: MerkleCommit::new(32, seed.clone()).commit(&other).unwrap(),
+            claim: statement(b"status=ok").claim,
+        };
+        assert!(prover.verify(&other_statement, &proof).is_err());
+        assert!(matches!(
+            prover.prove(&other_statement, &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+
+        // Leaves are salted: an opened leaf is not the hash of its chunk, the commitment depends
+        // on the seed, and proving needs the seed the document was committed with
+        assert_ne!(tree.leaf(2), hash(&plaintext[64 .. 96]));
+        let reseeded = MerkleCommit::new(32, b"another secret seed for the log".to_vec());
+        assert_ne!(reseeded.commit(&plaintext).unwrap(), commitment);
+        let wrong_seed = Witness {
+            plaintext: plaintext.clone(),
+            salt: b"another secret seed for the log".to_vec(),
+        };
+        assert!(matches!(
+            prover.prove(&statement(b"status=ok"), &wrong_seed),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+        assert!(matches!(
+            MerkleCommit::new(32, vec![0; SALT_LEN - 1]).commit(&plaintext),
+            Err(ZkpError::ConfigurationError(_))
+        ));
+    }
+}
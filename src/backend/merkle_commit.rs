@@ -0,0 +1,211 @@
+use crate::{
+    core::{Commit, Commitment},
+    error::ZkpError,
+    hash::sha256::hash,
+};
+
+/// Domain tag hashed in front of every internal node, so a node never hashes like a chunk
+const NODE_TAG: u8 = 0x01;
+
+/// Domain tag of the per-chunk salt derivation
+const SALT_TAG: &[u8] = b"zkp-rs/merkle-salt";
+
+/// Bytes of salt hashed in front of every chunk
+pub const SALT_LEN: usize = 16;
+
+/// Leaf used to pad the tree to a power of two
+const EMPTY_LEAF: [u8; 32] = [0; 32];
+
+/// Commits to a document split into `chunk_size`-byte chunks.
+///
+/// The commitment is the root of a SHA-256 Merkle tree over the chunk leaves followed by the
+/// number of chunks as a big-endian `u64`. Leaf `i` is `SHA256(salt_i || chunk_i)`, which is
+/// exactly what the substring circuit proves over the salted chunk, so a claim about a few
+/// bytes only has to hash the chunks holding them. The chunk count fixes the tree depth, which
+/// keeps a verifier from accepting an internal node as a leaf.
+///
+/// The salts are derived from a private seed (see [`chunk_salt`]) that the prover passes back
+/// as [`Witness::salt`](crate::core::Witness::salt). Without them, a leaf or sibling hash
+/// published in a proof would let anyone test guesses for a chunk offline. The seed must be
+/// secret and should be fresh for every document: equal chunks at the same index of two
+/// documents committed with one seed have equal leaves.
+pub struct MerkleCommit {
+    pub chunk_size: usize,
+    seed: Vec<u8>,
+}
+
+impl MerkleCommit {
+    pub fn new(chunk_size: usize, seed: Vec<u8>) -> Self {
+        Self { chunk_size, seed }
+    }
+}
+
+impl Commit for MerkleCommit {
+    fn commit(&self, input: &[u8]) -> Result<Commitment, ZkpError> {
+        Ok(MerkleTree::new(input, self.chunk_size, &self.seed)?.commitment())
+    }
+}
+
+/// Salt of chunk `index`: the first [`SALT_LEN`] bytes of `SHA256(tag || seed || index)`
+pub fn chunk_salt(seed: &[u8], index: usize) -> [u8; SALT_LEN] {
+    let mut preimage = SALT_TAG.to_vec();
+    preimage.extend_from_slice(seed);
+    preimage.extend_from_slice(&(index as u64).to_be_bytes());
+    hash(&preimage)[.. SALT_LEN].try_into().unwrap()
+}
+
+/// Leaf of a chunk: `SHA256(salt || chunk)`
+pub fn leaf_hash(salt: &[u8; SALT_LEN], chunk: &[u8]) -> [u8; 32] {
+    hash(&[&salt[..], chunk].concat())
+}
+
+/// SHA-256 Merkle tree over the salted fixed-size chunks of a document
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    chunk_size: usize,
+    chunks: usize,
+    /// Salt of every chunk
+    salts: Vec<[u8; SALT_LEN]>,
+    /// Node hashes level by level, from the padded leaves up to the root
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Split `data` into chunks, salt them from `seed` and build the tree. An empty document is
+    /// a single empty chunk.
+    pub fn new(data: &[u8], chunk_size: usize, seed: &[u8]) -> Result<Self, ZkpError> {
+        if chunk_size == 0 {
+            return Err(ZkpError::ConfigurationError(
+                "Merkle chunk size must be positive".to_string(),
+            ));
+        }
+        if seed.len() < SALT_LEN {
+            return Err(ZkpError::ConfigurationError(format!(
+                "Merkle salt seed must have at least {} bytes",
+                SALT_LEN
+            )));
+        }
+
+        let mut pieces: Vec<&[u8]> = data.chunks(chunk_size).collect();
+        if pieces.is_empty() {
+            pieces.push(&[]);
+        }
+        let chunks = pieces.len();
+        let salts: Vec<[u8; SALT_LEN]> = (0 .. chunks).map(|i| chunk_salt(seed, i)).collect();
+        let mut leaves: Vec<[u8; 32]> = salts
+            .iter()
+            .zip(pieces)
+            .map(|(salt, chunk)| leaf_hash(salt, chunk))
+            .collect();
+        leaves.resize(chunks.next_power_of_two(), EMPTY_LEAF);
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| node_hash(&pair[0], &pair[1]))
+                .collect();
+            levels.push(level);
+        }
+
+        Ok(Self {
+            chunk_size,
+            chunks,
+            salts,
+            levels,
+        })
+    }
+
+    /// Number of chunks (leaves before padding)
+    pub fn num_chunks(&self) -> usize {
+        self.chunks
+    }
+
+    /// Byte range of chunk `index` within a document of `len` bytes
+    pub fn chunk_range(&self, index: usize, len: usize) -> std::ops::Range<usize> {
+        let start = index * self.chunk_size;
+        start .. (start + self.chunk_size).min(len)
+    }
+
+    /// Salt of chunk `index`
+    pub fn salt(&self, index: usize) -> [u8; SALT_LEN] {
+        self.salts[index]
+    }
+
+    /// Leaf of chunk `index`
+    pub fn leaf(&self, index: usize) -> [u8; 32] {
+        self.levels[0][index]
+    }
+
+    /// Root of the tree
+    pub fn root(&self) -> [u8; 32] {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Sibling hashes from leaf `index` up to the root
+    pub fn path(&self, index: usize) -> Vec<[u8; 32]> {
+        let depth = self.levels.len() - 1;
+        (0 .. depth)
+            .map(|level| self.levels[level][(index >> level) ^ 1])
+            .collect()
+    }
+
+    /// Root followed by the chunk count
+    pub fn commitment(&self) -> Commitment {
+        let mut inner = self.root().to_vec();
+        inner.extend_from_slice(&(self.chunks as u64).to_be_bytes());
+        Commitment { inner }
+    }
+}
+
+/// Hash of an internal node
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(65);
+    preimage.push(NODE_TAG);
+    preimage.extend_from_slice(left);
+    preimage.extend_from_slice(right);
+    hash(&preimage)
+}
+
+/// Split a [`MerkleCommit`] commitment into the root and the chunk count
+pub fn decode_commitment(commitment: &Commitment) -> Result<([u8; 32], usize), ZkpError> {
+    if commitment.inner.len() != 40 {
+        return Err(ZkpError::InvalidPublicInput(
+            "Merkle commitment must be a 32-byte root and a 8-byte chunk count".to_string(),
+        ));
+    }
+    let root = commitment.inner[.. 32].try_into().unwrap();
+    let chunks = u64::from_be_bytes(commitment.inner[32 ..].try_into().unwrap());
+    match usize::try_from(chunks) {
+        Ok(chunks) if chunks > 0 => Ok((root, chunks)),
+        _ => Err(ZkpError::InvalidPublicInput(
+            "Invalid Merkle chunk count".to_string(),
+        )),
+    }
+}
+
+/// Check that `leaf` is chunk `index` of the tree committed by `root` with `chunks` chunks
+pub fn verify_path(
+    root: &[u8; 32],
+    chunks: usize,
+    index: usize,
+    leaf: &[u8; 32],
+    siblings: &[[u8; 32]],
+) -> bool {
+    let depth = chunks.next_power_of_two().trailing_zeros() as usize;
+    if index >= chunks || siblings.len() != depth {
+        return false;
+    }
+
+    let node = siblings
+        .iter()
+        .enumerate()
+        .fold(*leaf, |node, (level, sibling)| {
+            if (index >> level) & 1 == 0 {
+                node_hash(&node, sibling)
+            } else {
+                node_hash(sibling, &node)
+            }
+        });
+    node == *root
+}
@@ -1,4 +1,13 @@
-use crate::core::{Claim, Proof, Prove, Statement, Witness};
+use crate::{
+    circuits::{
+        dfa::Dfa,
+        normalize::normalized_occurrences,
+        number::read_number,
+        occurrence::{masked_occurrences, occurrences},
+    },
+    core::{Claim, Proof, Prove, Statement, Witness},
+    error::ZkpError,
+};
 
 /// 一个假的 Prover 实现：
 /// - prove: 把 claim + witness 拼接成一个假的 proof
@@ -6,28 +15,223 @@ use crate::core::{Claim, Proof, Prove, Statement, Witness};
 pub struct MockProver;
 
 impl Prove for MockProver {
-    fn prove(&self, statement: &Statement, witness: &Witness) -> Proof {
+    fn prove(&self, statement: &Statement, witness: &Witness) -> Result<Proof, ZkpError> {
         let mut proof_data = Vec::new();
 
         // 把 claim 序列化成字符串放入 proof
         match &statement.claim {
-            Claim::Substring { value } => {
+            Claim::Substring { value } | Claim::ChunkedSubstring { value } => {
                 proof_data.extend_from_slice(value.as_bytes());
             }
+            // 其余命题都要在完整原文上检查，proof 中只放原文
+            Claim::AnchoredSubstring { .. }
+            | Claim::Range { .. }
+            | Claim::Regex { .. }
+            | Claim::NotContains { .. }
+            | Claim::All(_)
+            | Claim::Ordered { .. }
+            | Claim::SubstringAt { .. }
+            | Claim::NthOccurrence { .. }
+            | Claim::Count { .. }
+            | Claim::Prefix { .. }
+            | Claim::Suffix { .. }
+            | Claim::NormalizedSubstring { .. }
+            | Claim::MaskedSubstring { .. }
+            | Claim::Redacted { .. } => {}
         }
 
         // 再加上 witness 的原文
         proof_data.extend_from_slice(&witness.plaintext);
 
-        Proof { inner: proof_data }
+        Ok(Proof { inner: proof_data })
     }
 
-    fn verify(&self, statement: &Statement, proof: &Proof) -> bool {
+    fn verify(&self, statement: &Statement, proof: &Proof) -> Result<(), ZkpError> {
         match &statement.claim {
-            Claim::Substring { value } => {
+            Claim::Substring { value } | Claim::ChunkedSubstring { value } => {
                 // 验证逻辑：witness 原文包含了 claim.value
                 // 这里简单地从 proof 中恢复 witness 并检查
-                String::from_utf8_lossy(&proof.inner).contains(value)
+                if String::from_utf8_lossy(&proof.inner).contains(value) {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Substring not found in mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::AnchoredSubstring {
+                anchor,
+                target,
+                length,
+            } => {
+                // 锚文本与目标子串都出现在同一份原文中，且长度与公开值一致
+                let plaintext = String::from_utf8_lossy(&proof.inner);
+                if !plaintext.contains(anchor) || !plaintext.contains(target) {
+                    return Err(ZkpError::ProofVerificationFailed(
+                        "Anchor or target not found in mock proof".to_string(),
+                    ));
+                }
+                if length.is_some_and(|length| length != proof.inner.len()) {
+                    return Err(ZkpError::ProofVerificationFailed(
+                        "Plaintext length mismatch in mock proof".to_string(),
+                    ));
+                }
+                Ok(())
+            }
+            Claim::Range { label, min, max } => {
+                // 标签的某次出现之后紧跟一个落在区间内的十进制数
+                let plaintext = &proof.inner;
+                let in_range = (0 .. plaintext.len())
+                    .filter(|&i| plaintext[i ..].starts_with(label.as_bytes()))
+                    .filter_map(|i| read_number(plaintext, i + label.len()))
+                    .any(|(value, _)| (*min ..= *max).contains(&value));
+                if in_range {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "No value in range after the label in mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::NotContains { value } => {
+                // 空串总是被包含
+                let plaintext = &proof.inner;
+                let present = value.is_empty()
+                    || plaintext
+                        .windows(value.len())
+                        .any(|window| window == value.as_bytes());
+                if present {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Absent string found in mock proof".to_string(),
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            Claim::All(claims) => claims.iter().try_for_each(|claim| {
+                let statement = Statement {
+                    commitment: statement.commitment.clone(),
+                    claim: claim.clone(),
+                };
+                self.verify(&statement, proof)
+            }),
+            Claim::Regex { pattern } => {
+                if Dfa::compile(pattern)?.is_match(&proof.inner) {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Pattern does not match mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::Ordered { values } => {
+                // 依次取每个子串在上一个子串结束之后的第一次出现
+                let plaintext = &proof.inner;
+                let mut from = 0;
+                for value in values {
+                    let position = plaintext[from ..]
+                        .windows(value.len().max(1))
+                        .position(|window| window == value.as_bytes())
+                        .ok_or(ZkpError::ProofVerificationFailed(
+                            "Ordered substrings not found in mock proof".to_string(),
+                        ))?;
+                    from += position + value.len();
+                }
+                Ok(())
+            }
+            Claim::SubstringAt { value, offset } => {
+                let end = offset.checked_add(value.len());
+                if end.and_then(|end| proof.inner.get(*offset .. end)) == Some(value.as_bytes()) {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Substring not at the public offset in mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::NthOccurrence { value, n } => {
+                if *n > 0
+                    && occurrences(&proof.inner, value.as_bytes())
+                        .nth(n - 1)
+                        .is_some()
+                {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Too few occurrences in mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::Count { value, min, max } => {
+                let count = occurrences(&proof.inner, value.as_bytes()).count();
+                if !value.is_empty() && (*min ..= *max).contains(&count) {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Occurrence count out of range in mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::Prefix { value, length } | Claim::Suffix { value, length } => {
+                let plaintext = &proof.inner;
+                let anchored = match &statement.claim {
+                    Claim::Prefix { .. } => plaintext.starts_with(value.as_bytes()),
+                    _ => plaintext.ends_with(value.as_bytes()),
+                };
+                if !anchored {
+                    return Err(ZkpError::ProofVerificationFailed(
+                        "Prefix or suffix not found in mock proof".to_string(),
+                    ));
+                }
+                if length.is_some_and(|length| length != plaintext.len()) {
+                    return Err(ZkpError::ProofVerificationFailed(
+                        "Plaintext length mismatch in mock proof".to_string(),
+                    ));
+                }
+                Ok(())
+            }
+            Claim::NormalizedSubstring {
+                value,
+                normalization,
+            } => {
+                let value = normalization.apply(value.as_bytes());
+                let rules = normalization.rules();
+                if !value.is_empty()
+                    && !normalized_occurrences(&proof.inner, &value, &rules).is_empty()
+                {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Normalized substring not found in mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::MaskedSubstring { value, hidden } => {
+                let found = hidden.len() == value.len()
+                    && masked_occurrences(&proof.inner, value.as_bytes(), hidden)
+                        .next()
+                        .is_some();
+                if found {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Masked substring not found in mock proof".to_string(),
+                    ))
+                }
+            }
+            Claim::Redacted { document, hidden } => {
+                // 等长，且未涂黑的字节逐位置相等
+                let plaintext = &proof.inner;
+                let derived = plaintext.len() == document.len()
+                    && hidden.len() == document.len()
+                    && (0 .. document.len()).all(|k| hidden[k] || plaintext[k] == document[k]);
+                if derived {
+                    Ok(())
+                } else {
+                    Err(ZkpError::ProofVerificationFailed(
+                        "Redacted document does not match mock proof".to_string(),
+                    ))
+                }
             }
         }
     }
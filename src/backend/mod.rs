@@ -1,3 +1,6 @@
+pub mod chunked_prover;
+pub mod merkle_commit;
 pub mod mock;
 pub mod sha256_commit;
+pub mod stark_config;
 pub mod stark_prover;
@@ -1,15 +1,16 @@
 use crate::{
     core::{Commit, Commitment},
+    error::ZkpError,
     hash::sha256::hash,
 };
 
 pub struct Sha256Commit;
 
 impl Commit for Sha256Commit {
-    fn commit(&self, input: &[u8]) -> Commitment {
+    fn commit(&self, input: &[u8]) -> Result<Commitment, ZkpError> {
         let hash = hash(input);
-        Commitment {
+        Ok(Commitment {
             inner: hash.to_vec(),
-        }
+        })
     }
 }
@@ -0,0 +1,130 @@
+use p3_challenger::{HashChallenger, SerializingChallenger64};
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_fri::{FriParameters, HidingFriPcs, TwoAdicFriPcs};
+use p3_goldilocks::Goldilocks;
+use p3_merkle_tree::{MerkleTreeHidingMmcs, MerkleTreeMmcs};
+use p3_sha256::Sha256;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher};
+use p3_uni_stark::StarkConfig;
+use rand::{SeedableRng, rngs::StdRng};
+
+/// Trace base field
+pub type Val = Goldilocks;
+
+/// Extension field used for out-of-domain openings and FRI folding challenges
+pub type Challenge = BinomialExtensionField<Val, 2>;
+
+type ByteHash = Sha256;
+type FieldHash = SerializingHasher<ByteHash>;
+type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
+type ValMmcs = MerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Dft = Radix2DitParallel<Val>;
+type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+
+/// Randomness for the hiding PCS (salts and random codewords), seeded from the OS
+pub type HidingRng = StdRng;
+type HidingValMmcs =
+    MerkleTreeHidingMmcs<Val, u8, FieldHash, MyCompress, HidingRng, 32, SALT_ELEMS>;
+type HidingChallengeMmcs = ExtensionMmcs<Val, Challenge, HidingValMmcs>;
+type HidingPcs = HidingFriPcs<Val, Dft, HidingValMmcs, HidingChallengeMmcs, HidingRng>;
+
+/// Fiat-Shamir challenger over a SHA-256 transcript
+pub type Challenger = SerializingChallenger64<Val, HashChallenger<u8, ByteHash, 32>>;
+
+/// Full uni-stark configuration: SHA-256 Merkle commitments + FRI over Goldilocks
+pub type StarkCfg = StarkConfig<Pcs, Challenge, Challenger>;
+
+/// Serializable uni-stark proof for [`StarkCfg`]
+pub type StarkProof = p3_uni_stark::Proof<StarkCfg>;
+
+/// Zero-knowledge uni-stark configuration: salted Merkle leaves + hiding FRI
+pub type HidingStarkCfg = StarkConfig<HidingPcs, Challenge, Challenger>;
+
+/// Serializable uni-stark proof for [`HidingStarkCfg`]
+pub type HidingStarkProof = p3_uni_stark::Proof<HidingStarkCfg>;
+
+/// LDE blowup (log2). The quotient domain must fit inside the LDE, so the AIR may use
+/// constraints up to degree `2^LOG_BLOWUP + 1`.
+pub const LOG_BLOWUP: usize = 3;
+
+/// Number of FRI query rounds
+pub const NUM_QUERIES: usize = 28;
+
+/// Proof-of-work grinding bits before the query phase
+pub const PROOF_OF_WORK_BITS: usize = 16;
+
+/// Random field elements salted into every Merkle leaf of the hiding configuration
+pub const SALT_ELEMS: usize = 4;
+
+/// Random codewords the hiding PCS appends to each committed batch, so that the values
+/// opened at FRI query points are masked
+pub const NUM_RANDOM_CODEWORDS: usize = 4;
+
+/// Domain-separation tag prefixed to every Fiat-Shamir transcript
+pub const TRANSCRIPT_DOMAIN: &[u8] = b"zkp-rs/substring-stark/v1";
+
+/// Build the STARK configuration shared by prover and verifier
+///
+/// `transcript_seed` is absorbed into the challenger before any trace commitment, so every
+/// challenge (and therefore the proof) depends on it. Callers pass the statement and circuit
+/// configuration here; a proof made under one seed fails to verify under any other.
+pub fn build_stark_config(transcript_seed: &[u8]) -> StarkCfg {
+    let byte_hash = ByteHash {};
+    let field_hash = FieldHash::new(byte_hash);
+    let compress = MyCompress::new(byte_hash);
+    let val_mmcs = ValMmcs::new(field_hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    let fri_params = FriParameters {
+        log_blowup: LOG_BLOWUP,
+        log_final_poly_len: 0,
+        num_queries: NUM_QUERIES,
+        proof_of_work_bits: PROOF_OF_WORK_BITS,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_params);
+    let challenger = Challenger::from_hasher(transcript_prefix(transcript_seed), byte_hash);
+
+    StarkCfg::new(pcs, challenger)
+}
+
+/// Build the zero-knowledge counterpart of [`build_stark_config`]
+///
+/// The hiding PCS randomizes the committed trace (extra random rows and blinding codewords)
+/// and salts every Merkle leaf, so openings at FRI query points reveal nothing about the
+/// witness columns. Only the prover's randomness matters; the verifier ignores it.
+pub fn build_hiding_stark_config(transcript_seed: &[u8]) -> HidingStarkCfg {
+    let byte_hash = ByteHash {};
+    let field_hash = FieldHash::new(byte_hash);
+    let compress = MyCompress::new(byte_hash);
+    let val_mmcs = HidingValMmcs::new(field_hash, compress, HidingRng::from_os_rng());
+    let challenge_mmcs = HidingChallengeMmcs::new(val_mmcs.clone());
+
+    let fri_params = FriParameters {
+        log_blowup: LOG_BLOWUP,
+        log_final_poly_len: 0,
+        num_queries: NUM_QUERIES,
+        proof_of_work_bits: PROOF_OF_WORK_BITS,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = HidingPcs::new(
+        Dft::default(),
+        val_mmcs,
+        fri_params,
+        NUM_RANDOM_CODEWORDS,
+        HidingRng::from_os_rng(),
+    );
+    let challenger = Challenger::from_hasher(transcript_prefix(transcript_seed), byte_hash);
+
+    HidingStarkCfg::new(pcs, challenger)
+}
+
+/// Initial challenger state: domain tag followed by the caller's seed
+fn transcript_prefix(transcript_seed: &[u8]) -> Vec<u8> {
+    let mut initial_state = TRANSCRIPT_DOMAIN.to_vec();
+    initial_state.extend_from_slice(transcript_seed);
+    initial_state
+}
@@ -1,20 +1,44 @@
-use p3_field::PrimeField64;
 use p3_goldilocks::Goldilocks;
-use sha2::{Digest, Sha256};
+use p3_matrix::dense::RowMajorMatrix;
 
 use crate::{
+    backend::stark_config::{
+        HidingStarkProof, LOG_BLOWUP, NUM_QUERIES, PROOF_OF_WORK_BITS, StarkProof,
+        build_hiding_stark_config, build_stark_config,
+    },
     circuits::{
-        Circuit, air::SubstringAIR, substring_circuit::SubstringCircuit, trace::TraceLayout,
+        Circuit,
+        air::SubstringAIR,
+        dfa::Dfa,
+        normalize::normalized_occurrences,
+        number::VALUE_BITS,
+        occurrence::{COUNT_BITS, masked_occurrences, occurrences},
+        redaction::{self, MAX_REDACTION_BOUNDARIES},
+        substring_circuit::SubstringCircuit,
+        trace::{TraceLayout, to_row_major},
+    },
+    config::{
+        CircuitConfig, CircuitParams, CircuitWitness, MaskedWindow, NormalizedWindow,
+        NthOccurrence, OccurrenceCount, PublicInputs, RedactedDocument, ValueRange, WindowInput,
+        WindowOrder, WindowPosition,
     },
-    config::{CircuitConfig, CircuitParams, CircuitWitness, PublicInputs},
-    core::{Claim, Proof, Prove, Statement, Witness},
+    core::{BackendId, Claim, ClaimKind, FieldId, Proof, ProofEnvelope, Prove, Statement, Witness},
     error::ZkpError,
+    hash::sha256,
 };
 
+/// Encoding of the Plonky3 proof inside the envelope. Fixed-width integers keep the proof
+/// size independent of the opened values, so the length leaks nothing about the witness.
+const PROOF_ENCODING: bincode::config::Configuration<
+    bincode::config::LittleEndian,
+    bincode::config::Fixint,
+> = bincode::config::standard().with_fixed_int_encoding();
+
 /// STARK prover implementation using Plonky3
 pub struct StarkProver {
     circuit: SubstringCircuit,
     config: CircuitConfig,
+    hiding: bool,
 }
 
 impl StarkProver {
@@ -22,249 +46,622 @@ impl StarkProver {
     pub fn new(config: CircuitConfig) -> Self {
         let circuit = SubstringCircuit::new(config.clone());
 
-        Self { circuit, config }
+        Self {
+            circuit,
+            config,
+            hiding: false,
+        }
     }
 
-    /// Extract offset from witness data
-    fn extract_offset(&self, witness: &Witness, statement: &Statement) -> Result<usize, ZkpError> {
-        let substring = match &statement.claim {
-            Claim::Substring { value } => value,
-        };
-
-        let plaintext = String::from_utf8_lossy(&witness.plaintext);
+    /// Create a zero-knowledge STARK prover
+    ///
+    /// Proofs are produced with the hiding PCS, so opened trace values are masked by
+    /// fresh randomness and two proofs for the same public inputs look alike whatever the
+    /// plaintext. Hiding proofs are only accepted by a hiding verifier.
+    pub fn new_hiding(config: CircuitConfig) -> Self {
+        Self {
+            hiding: true,
+            ..Self::new(config)
+        }
+    }
 
-        // Find the offset of the substring in the plaintext
-        if let Some(offset) = plaintext.find(substring) {
-            Ok(offset)
+    /// Backend identifier recorded in the proof envelope
+    pub(crate) fn backend_id(&self) -> BackendId {
+        if self.hiding {
+            BackendId::Plonky3UniStarkHiding
         } else {
-            Err(ZkpError::InvalidWitness(
-                "Substring not found in plaintext".to_string(),
-            ))
+            BackendId::Plonky3UniStark
+        }
+    }
+
+    /// Seed for the Fiat-Shamir transcript: the configuration hash, the commitment bytes
+    /// and the encoded claim
+    fn transcript_seed(&self, statement: &Statement) -> Result<Vec<u8>, ZkpError> {
+        let mut seed = self.config_hash()?.to_vec();
+        let statement_bytes = bincode::serde::encode_to_vec(statement, bincode::config::standard())
+            .map_err(|e| {
+                ZkpError::SerializationError(format!("Statement encoding failed: {}", e))
+            })?;
+        seed.extend_from_slice(&statement_bytes);
+        Ok(seed)
+    }
+
+    /// Hash of everything prover and verifier must agree on: the circuit configuration
+    /// and the FRI parameters
+    pub(crate) fn config_hash(&self) -> Result<[u8; 32], ZkpError> {
+        let encoded = bincode::serde::encode_to_vec(
+            (&self.config, LOG_BLOWUP, NUM_QUERIES, PROOF_OF_WORK_BITS),
+            bincode::config::standard(),
+        )
+        .map_err(|e| ZkpError::SerializationError(format!("Config encoding failed: {}", e)))?;
+        Ok(sha256::hash(&encoded))
+    }
+
+    /// Find the private byte offset of every window in the plaintext. Matching is done on raw
+    /// bytes, so non-UTF-8 plaintexts are handled. A window that must come after other windows
+    /// is searched for past their ends; orders always point back to an earlier window, so the
+    /// earliest such occurrence is found whenever one exists. Windows with a public offset take
+    /// it as is, windows proven to be the n-th occurrence take that occurrence, and prefix and
+    /// suffix windows sit at the ends of the plaintext; all of these are checked by the circuit.
+    /// Normalized windows are searched for in the normalized plaintext and placed at the first
+    /// byte of the character their match starts with; masked windows skip their hidden bytes.
+    fn extract_offsets(
+        &self,
+        witness: &Witness,
+        public_inputs: &PublicInputs,
+    ) -> Result<Vec<usize>, ZkpError> {
+        let plaintext = &witness.plaintext;
+        let windows = &public_inputs.windows;
+
+        let mut offsets: Vec<usize> = Vec::with_capacity(windows.len());
+        for (w, window) in windows.iter().enumerate() {
+            if let Some(position) = public_inputs
+                .positions
+                .iter()
+                .find(|position| position.window == w)
+            {
+                offsets.push(position.offset);
+                continue;
+            }
+            if let Some(nth) = public_inputs
+                .nth_occurrences
+                .iter()
+                .find(|nth| nth.window == w)
+            {
+                let offset = occurrences(plaintext, &window.bytes)
+                    .nth(nth.n.saturating_sub(1))
+                    .ok_or(ZkpError::InvalidWitness(format!(
+                        "Substring occurs fewer than {} times in plaintext",
+                        nth.n
+                    )))?;
+                offsets.push(offset);
+                continue;
+            }
+            if let Some(normalized) = public_inputs
+                .normalized
+                .iter()
+                .find(|normalized| normalized.window == w)
+            {
+                let rules = normalized.normalization.rules();
+                let offset = *normalized_occurrences(plaintext, &window.bytes, &rules)
+                    .first()
+                    .ok_or(ZkpError::InvalidWitness(
+                        "Normalized substring not found in plaintext".to_string(),
+                    ))?;
+                offsets.push(offset);
+                continue;
+            }
+            if let Some(masked) = public_inputs
+                .masked
+                .iter()
+                .find(|masked| masked.window == w)
+            {
+                let offset = masked_occurrences(plaintext, &window.bytes, &masked.hidden)
+                    .next()
+                    .ok_or(ZkpError::InvalidWitness(
+                        "Masked substring not found in plaintext".to_string(),
+                    ))?;
+                offsets.push(offset);
+                continue;
+            }
+            if public_inputs.prefixes.contains(&w) {
+                offsets.push(0);
+                continue;
+            }
+            if public_inputs.suffixes.contains(&w) {
+                offsets.push(plaintext.len().saturating_sub(window.bytes.len()));
+                continue;
+            }
+
+            let from = public_inputs
+                .orders
+                .iter()
+                .filter(|order| order.after == w)
+                .map(|order| offsets[order.before] + windows[order.before].bytes.len())
+                .max()
+                .unwrap_or(0);
+
+            // Find the first occurrence of the window bytes at or after `from`
+            let offset = occurrences(plaintext, &window.bytes)
+                .find(|&offset| offset >= from)
+                .ok_or(ZkpError::InvalidWitness(
+                    "Substring not found in plaintext".to_string(),
+                ))?;
+            offsets.push(offset);
         }
+        Ok(offsets)
     }
 
-    /// Build circuit parameters from statement and witness
+    /// Build circuit parameters from the public inputs and the witness. Without explicit
+    /// offsets every window is placed by [`Self::extract_offsets`].
     fn build_circuit_params(
         &self,
-        statement: &Statement,
+        public_inputs: PublicInputs,
         witness: &Witness,
+        offsets: Option<Vec<usize>>,
     ) -> Result<CircuitParams, ZkpError> {
-        let substring = match &statement.claim {
-            Claim::Substring { value } => value.clone(),
+        let offsets = match offsets {
+            Some(offsets) => offsets,
+            None => self.extract_offsets(witness, &public_inputs)?,
         };
 
-        let offset = self.extract_offset(witness, statement)?;
-
         Ok(CircuitParams {
             config: self.config.clone(),
-            public_inputs: PublicInputs {
-                commitment: statement.commitment.inner.clone().try_into().map_err(|_| {
-                    ZkpError::InvalidPublicInput("Invalid commitment length".to_string())
-                })?,
-                substring: substring.into_bytes(),
-            },
+            public_inputs,
             witness: Some(CircuitWitness {
                 plaintext: witness.plaintext.clone(),
-                offset,
+                offsets,
             }),
         })
     }
-}
 
-impl Prove for StarkProver {
-    fn prove(&self, statement: &Statement, witness: &Witness) -> Proof {
+    /// Trace layout for the statement: one window per public string in the claim, plus the
+    /// number gadget for range claims
+    fn layout_for(&self, public_inputs: &PublicInputs) -> TraceLayout {
+        TraceLayout::for_inputs(&self.config, public_inputs)
+    }
+
+    /// Prove with explicitly chosen window offsets instead of the default search.
+    ///
+    /// `offsets` holds one byte offset per window of the claim, in the order the claim lists
+    /// its public strings (for `Claim::All`, the sub-claims' windows one after another). This
+    /// picks which occurrence of a substring is proven; the offsets stay private and every
+    /// positional requirement of the claim is still enforced by the circuit.
+    pub fn prove_with_offsets(
+        &self,
+        statement: &Statement,
+        witness: &Witness,
+        offsets: Vec<usize>,
+    ) -> Result<Proof, ZkpError> {
+        self.prove_with(statement, witness, Some(offsets))
+    }
+
+    fn prove_with(
+        &self,
+        statement: &Statement,
+        witness: &Witness,
+        offsets: Option<Vec<usize>>,
+    ) -> Result<Proof, ZkpError> {
+        let public_inputs = self.extract_public_inputs(statement)?;
+        self.prove_inputs(statement, public_inputs, witness, offsets)
+    }
+
+    /// Prove public inputs that were lowered by the caller rather than from the claim. The
+    /// transcript is still bound to `statement`, and the envelope records its claim kind.
+    pub(crate) fn prove_inputs(
+        &self,
+        statement: &Statement,
+        public_inputs: PublicInputs,
+        witness: &Witness,
+        offsets: Option<Vec<usize>>,
+    ) -> Result<Proof, ZkpError> {
         // Build circuit parameters
-        let params = match self.build_circuit_params(statement, witness) {
-            Ok(params) => params,
-            Err(e) => {
-                // Return a proof that will fail verification
-                return Proof {
-                    inner: format!("ERROR: {}", e).into_bytes(),
-                };
-            }
-        };
+        let params = self.build_circuit_params(public_inputs, witness, offsets)?;
 
         // Generate execution trace using the circuit
-        let trace = match self.circuit.generate_trace(&params) {
-            Ok(trace) => trace,
-            Err(e) => {
-                return Proof {
-                    inner: format!("TRACE_ERROR: {}", e).into_bytes(),
-                };
-            }
-        };
+        let trace = self.circuit.generate_trace(&params)?;
 
-        // Verify constraints are satisfied
-        if let Err(e) = self.circuit.verify_constraints(&trace, &params) {
-            return Proof {
-                inner: format!("CONSTRAINT_ERROR: {}", e).into_bytes(),
-            };
+        // Verify constraints are satisfied before spending time on the STARK
+        if !self.circuit.verify_constraints(&trace, &params)? {
+            return Err(ZkpError::ConstraintNotSatisfied(
+                "Witness does not satisfy the circuit".to_string(),
+            ));
         }
 
         // Create AIR for STARK proving
-        let layout = TraceLayout::default();
+        let layout = self.layout_for(&params.public_inputs);
         let air = SubstringAIR::new(self.config.clone(), layout);
 
-        // Convert trace to the format expected by Plonky3
-        let stark_trace = self.convert_trace_format(trace);
+        // Convert trace to the row-major format expected by Plonky3
+        let stark_trace = to_row_major(&trace);
 
-        // Generate STARK proof using Plonky3
-        match self.generate_stark_proof(air, stark_trace, &params) {
-            Ok(proof_bytes) => Proof { inner: proof_bytes },
-            Err(e) => Proof {
-                inner: format!("STARK_ERROR: {}", e).into_bytes(),
-            },
-        }
+        // Generate STARK proof using Plonky3 and wrap it in a versioned envelope
+        let seed = self.transcript_seed(statement)?;
+        let proof_bytes = self.generate_stark_proof(&seed, air, stark_trace, &params)?;
+        let envelope = ProofEnvelope::new(
+            self.backend_id(),
+            FieldId::Goldilocks,
+            ClaimKind::from(&statement.claim),
+            self.config_hash()?,
+            proof_bytes,
+        );
+        Ok(Proof {
+            inner: envelope.to_bytes()?,
+        })
     }
+}
 
-    fn verify(&self, statement: &Statement, proof: &Proof) -> bool {
-        // Check for error proofs first
-        if let Ok(error_msg) = String::from_utf8(proof.inner.clone()) {
-            if error_msg.starts_with("ERROR:")
-                || error_msg.starts_with("TRACE_ERROR:")
-                || error_msg.starts_with("CONSTRAINT_ERROR:")
-                || error_msg.starts_with("STARK_ERROR:")
-            {
-                return false;
-            }
-        }
+impl Prove for StarkProver {
+    fn prove(&self, statement: &Statement, witness: &Witness) -> Result<Proof, ZkpError> {
+        self.prove_with(statement, witness, None)
+    }
 
+    fn verify(&self, statement: &Statement, proof: &Proof) -> Result<(), ZkpError> {
         // Extract public inputs from statement
-        let public_inputs = match self.extract_public_inputs(statement) {
-            Ok(inputs) => inputs,
-            Err(_) => return false,
-        };
+        let public_inputs = self.extract_public_inputs(statement)?;
+        self.verify_inputs(statement, &public_inputs, proof)
+    }
+}
+
+impl StarkProver {
+    /// Verify a proof of public inputs lowered by the caller, see [`Self::prove_inputs`]
+    pub(crate) fn verify_inputs(
+        &self,
+        statement: &Statement,
+        public_inputs: &PublicInputs,
+        proof: &Proof,
+    ) -> Result<(), ZkpError> {
+        // Reject proofs from other formats, backends or configurations up front
+        let envelope = ProofEnvelope::from_bytes(&proof.inner)?;
+        envelope.ensure_matches(
+            self.backend_id(),
+            FieldId::Goldilocks,
+            ClaimKind::from(&statement.claim),
+            &self.config_hash()?,
+        )?;
 
         // Create AIR for verification
-        let layout = TraceLayout::default();
+        let layout = self.layout_for(public_inputs);
         let air = SubstringAIR::new(self.config.clone(), layout);
 
-        // Verify STARK proof
-        self.verify_stark_proof(&air, &proof.inner, &public_inputs)
-            .unwrap_or(false)
+        // Verify STARK proof against a transcript bound to this statement
+        let seed = self.transcript_seed(statement)?;
+        self.verify_stark_proof(&seed, &air, &envelope.payload, public_inputs)
     }
 }
 
 impl StarkProver {
-    /// Convert trace format for Plonky3 compatibility
-    fn convert_trace_format(&self, trace: Vec<Vec<Goldilocks>>) -> Vec<Vec<Goldilocks>> {
-        // For now, return as-is since our trace format is already compatible
-        // In a full implementation, this might need matrix transposition or other formatting
-        trace
-    }
-
     /// Generate STARK proof using Plonky3
     fn generate_stark_proof(
         &self,
-        _air: SubstringAIR,
-        trace: Vec<Vec<Goldilocks>>,
+        transcript_seed: &[u8],
+        air: SubstringAIR,
+        trace: RowMajorMatrix<Goldilocks>,
         params: &CircuitParams,
     ) -> Result<Vec<u8>, ZkpError> {
-        // For MVP implementation, we'll create a simplified proof structure
-        // In a full implementation, this would use p3_uni_stark::prove
+        let public_values = air.public_values(&params.public_inputs);
 
-        // Serialize the proof components
-        let proof_data = StarkProofData {
-            trace_commitment: self.compute_trace_commitment(&trace)?,
-            public_inputs: params.public_inputs.clone(),
-            fri_proof: self.generate_fri_proof(&trace)?,
-            circuit_config: params.config.clone(),
+        let encoded = if self.hiding {
+            let stark_config = build_hiding_stark_config(transcript_seed);
+            let proof = p3_uni_stark::prove(&stark_config, &air, trace, &public_values);
+            bincode::serde::encode_to_vec(&proof, PROOF_ENCODING)
+        } else {
+            let stark_config = build_stark_config(transcript_seed);
+            let proof = p3_uni_stark::prove(&stark_config, &air, trace, &public_values);
+            bincode::serde::encode_to_vec(&proof, PROOF_ENCODING)
         };
 
-        // Serialize to bytes
-        serde_json::to_vec(&proof_data)
-            .map_err(|e| ZkpError::SerializationError(format!("Serialization failed: {}", e)))
+        encoded.map_err(|e| ZkpError::SerializationError(format!("Serialization failed: {}", e)))
     }
 
     /// Verify STARK proof
     fn verify_stark_proof(
         &self,
-        _air: &SubstringAIR,
+        transcript_seed: &[u8],
+        air: &SubstringAIR,
         proof_bytes: &[u8],
         public_inputs: &PublicInputs,
-    ) -> Result<bool, ZkpError> {
-        // Deserialize proof
-        let proof_data: StarkProofData = serde_json::from_slice(proof_bytes)
-            .map_err(|e| ZkpError::SerializationError(format!("Deserialization failed: {}", e)))?;
-
-        // Verify public inputs match
-        if proof_data.public_inputs.commitment != public_inputs.commitment
-            || proof_data.public_inputs.substring != public_inputs.substring
-        {
-            return Ok(false);
-        }
+    ) -> Result<(), ZkpError> {
+        let deserialization_failed = |e: bincode::error::DecodeError| {
+            ZkpError::SerializationError(format!("Deserialization failed: {}", e))
+        };
 
-        // In a full implementation, this would verify:
-        // 1. FRI proof validity
-        // 2. Trace commitment correctness
-        // 3. Constraint satisfaction
+        let public_values = air.public_values(public_inputs);
 
-        // For MVP, we do basic checks
-        Ok(self.basic_proof_validation(&proof_data))
+        if self.hiding {
+            let (proof, _): (HidingStarkProof, usize) =
+                bincode::serde::decode_from_slice(proof_bytes, PROOF_ENCODING)
+                    .map_err(deserialization_failed)?;
+            let stark_config = build_hiding_stark_config(transcript_seed);
+            p3_uni_stark::verify(&stark_config, air, &proof, &public_values)
+                .map_err(|e| ZkpError::ProofVerificationFailed(format!("{:?}", e)))
+        } else {
+            let (proof, _): (StarkProof, usize) =
+                bincode::serde::decode_from_slice(proof_bytes, PROOF_ENCODING)
+                    .map_err(deserialization_failed)?;
+            let stark_config = build_stark_config(transcript_seed);
+            p3_uni_stark::verify(&stark_config, air, &proof, &public_values)
+                .map_err(|e| ZkpError::ProofVerificationFailed(format!("{:?}", e)))
+        }
     }
 
     /// Extract public inputs from statement
     fn extract_public_inputs(&self, statement: &Statement) -> Result<PublicInputs, ZkpError> {
-        let substring = match &statement.claim {
-            Claim::Substring { value } => value.clone(),
-        };
-
-        Ok(PublicInputs {
-            commitment: statement.commitment.inner.clone().try_into().map_err(|_| {
+        let commitment =
+            statement.commitment.inner.clone().try_into().map_err(|_| {
                 ZkpError::InvalidPublicInput("Invalid commitment length".to_string())
-            })?,
-            substring: substring.into_bytes(),
-        })
-    }
+            })?;
 
-    /// Compute trace commitment (simplified)
-    fn compute_trace_commitment(&self, trace: &[Vec<Goldilocks>]) -> Result<Vec<u8>, ZkpError> {
-        let mut hasher = Sha256::new();
-        for row in trace {
-            for &field_element in row {
-                // Convert field element to bytes and hash
-                hasher.update(&field_element.as_canonical_u64().to_le_bytes());
-            }
-        }
-
-        Ok(hasher.finalize().to_vec())
+        let mut public_inputs = PublicInputs::new(commitment);
+        self.lower_claim(&statement.claim, &mut public_inputs)?;
+        Ok(public_inputs)
     }
 
-    /// Generate FRI proof (simplified)
-    fn generate_fri_proof(&self, trace: &[Vec<Goldilocks>]) -> Result<Vec<u8>, ZkpError> {
-        // Simplified FRI proof - in reality this would use p3_fri
-        let mut hasher = Sha256::new();
-        hasher.update(b"FRI_PROOF");
-        for row in trace {
-            for &element in row {
-                hasher.update(&element.as_canonical_u64().to_le_bytes());
+    /// Add the windows and gadgets of a claim to the public inputs. Sub-claims of
+    /// `Claim::All` are appended in order and share the plaintext and the hash.
+    fn lower_claim(&self, claim: &Claim, public_inputs: &mut PublicInputs) -> Result<(), ZkpError> {
+        match claim {
+            Claim::Substring { value } => {
+                public_inputs
+                    .windows
+                    .push(WindowInput::new(value.clone().into_bytes()));
+            }
+            Claim::AnchoredSubstring {
+                anchor,
+                target,
+                length,
+            } => {
+                public_inputs.windows.extend([
+                    WindowInput::new(anchor.clone().into_bytes()),
+                    WindowInput::new(target.clone().into_bytes()),
+                ]);
+                disclose_length(public_inputs, *length, self.config.max_text_len)?;
+            }
+            Claim::Range { label, min, max } => {
+                if label.is_empty() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Range claim needs a non-empty label".to_string(),
+                    ));
+                }
+                if min > max || *max >= 1 << VALUE_BITS {
+                    return Err(ZkpError::InvalidPublicInput(format!(
+                        "Invalid range [{}, {}] (bounds must be below 2^{})",
+                        min, max, VALUE_BITS
+                    )));
+                }
+                public_inputs.ranges.push(ValueRange {
+                    window: public_inputs.windows.len(),
+                    min: *min,
+                    max: *max,
+                });
+                public_inputs
+                    .windows
+                    .push(WindowInput::new(label.clone().into_bytes()));
+            }
+            Claim::Regex { pattern } => public_inputs.dfas.push(Dfa::compile(pattern)?),
+            Claim::NotContains { value } => {
+                public_inputs
+                    .absent
+                    .push(WindowInput::new(value.clone().into_bytes()));
+            }
+            Claim::All(claims) => {
+                if claims.is_empty() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Combined claim needs at least one sub-claim".to_string(),
+                    ));
+                }
+                for claim in claims {
+                    self.lower_claim(claim, public_inputs)?;
+                }
+            }
+            Claim::Ordered { values } => {
+                if values.len() < 2 || values.iter().any(String::is_empty) {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Ordered claim needs at least two non-empty values".to_string(),
+                    ));
+                }
+                // Consecutive windows, each ending before the next one starts
+                let first = public_inputs.windows.len();
+                public_inputs.windows.extend(
+                    values
+                        .iter()
+                        .map(|value| WindowInput::new(value.clone().into_bytes())),
+                );
+                public_inputs
+                    .orders
+                    .extend(
+                        (first .. first + values.len() - 1).map(|before| WindowOrder {
+                            before,
+                            after: before + 1,
+                        }),
+                    );
+            }
+            Claim::SubstringAt { value, offset } => {
+                if value.is_empty() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Public-offset claim needs a non-empty value".to_string(),
+                    ));
+                }
+                // The offset is reduced into the field, so it must be pinned to the plaintext
+                if offset
+                    .checked_add(value.len())
+                    .is_none_or(|end| end > self.config.max_text_len)
+                {
+                    return Err(ZkpError::InvalidPublicInput(format!(
+                        "Public offset {} leaves no room for the value in {} bytes",
+                        offset, self.config.max_text_len
+                    )));
+                }
+                public_inputs.positions.push(WindowPosition {
+                    window: public_inputs.windows.len(),
+                    offset: *offset,
+                });
+                public_inputs
+                    .windows
+                    .push(WindowInput::new(value.clone().into_bytes()));
+            }
+            Claim::NthOccurrence { value, n } => {
+                if value.is_empty() || *n == 0 {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Occurrence claim needs a non-empty value and n >= 1".to_string(),
+                    ));
+                }
+                // n is reduced into the field; a plaintext holds at most max_text_len occurrences
+                if *n > self.config.max_text_len {
+                    return Err(ZkpError::InvalidPublicInput(format!(
+                        "Occurrence number {} exceeds the maximum plaintext length {}",
+                        n, self.config.max_text_len
+                    )));
+                }
+                public_inputs.nth_occurrences.push(NthOccurrence {
+                    window: public_inputs.windows.len(),
+                    n: *n,
+                });
+                public_inputs
+                    .windows
+                    .push(WindowInput::new(value.clone().into_bytes()));
+            }
+            Claim::Count { value, min, max } => {
+                if value.is_empty() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Count claim needs a non-empty value".to_string(),
+                    ));
+                }
+                if min > max || *max >= 1 << COUNT_BITS {
+                    return Err(ZkpError::InvalidPublicInput(format!(
+                        "Invalid count range [{}, {}] (bounds must be below 2^{})",
+                        min, max, COUNT_BITS
+                    )));
+                }
+                public_inputs.counts.push(OccurrenceCount {
+                    bytes: value.clone().into_bytes(),
+                    min: *min,
+                    max: *max,
+                });
+            }
+            Claim::Prefix { value, length } | Claim::Suffix { value, length } => {
+                if value.is_empty() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Prefix and suffix claims need a non-empty value".to_string(),
+                    ));
+                }
+                let window = public_inputs.windows.len();
+                match claim {
+                    Claim::Prefix { .. } => public_inputs.prefixes.push(window),
+                    _ => public_inputs.suffixes.push(window),
+                }
+                public_inputs
+                    .windows
+                    .push(WindowInput::new(value.clone().into_bytes()));
+                disclose_length(public_inputs, *length, self.config.max_text_len)?;
+            }
+            Claim::NormalizedSubstring {
+                value,
+                normalization,
+            } => {
+                // The window holds the normalized value, compared against the normalized plaintext
+                let bytes = normalization.apply(value.as_bytes());
+                if bytes.is_empty() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Normalized substring claim needs a non-empty value".to_string(),
+                    ));
+                }
+                public_inputs.normalized.push(NormalizedWindow {
+                    window: public_inputs.windows.len(),
+                    normalization: *normalization,
+                });
+                public_inputs.windows.push(WindowInput::new(bytes));
+            }
+            Claim::MaskedSubstring { value, hidden } => {
+                if value.is_empty() || hidden.len() != value.len() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Masked substring claim needs a non-empty value and one flag per byte"
+                            .to_string(),
+                    ));
+                }
+                // Hidden bytes are zeroed so the public window says nothing about them
+                let bytes = value
+                    .bytes()
+                    .zip(hidden)
+                    .map(|(byte, &hidden)| if hidden { 0 } else { byte })
+                    .collect();
+                public_inputs.masked.push(MaskedWindow {
+                    window: public_inputs.windows.len(),
+                    hidden: hidden.clone(),
+                });
+                public_inputs.windows.push(WindowInput::new(bytes));
+            }
+            Claim::Redacted { document, hidden } => {
+                if document.is_empty() || hidden.len() != document.len() {
+                    return Err(ZkpError::InvalidPublicInput(
+                        "Redacted claim needs a non-empty document and one flag per byte"
+                            .to_string(),
+                    ));
+                }
+                // Every redaction boundary takes a column of the mask register
+                let boundaries = redaction::boundaries(hidden).len();
+                if boundaries > MAX_REDACTION_BOUNDARIES {
+                    return Err(ZkpError::InvalidPublicInput(format!(
+                        "Redaction has {} boundaries, at most {} are supported",
+                        boundaries, MAX_REDACTION_BOUNDARIES
+                    )));
+                }
+                // The document covers the whole plaintext from its first byte, so its length is
+                // public; redacted bytes are zeroed so the public values say nothing about them
+                disclose_length(
+                    public_inputs,
+                    Some(document.len()),
+                    self.config.max_text_len,
+                )?;
+                public_inputs.redacted.push(RedactedDocument {
+                    bytes: document
+                        .iter()
+                        .zip(hidden)
+                        .map(|(&byte, &hidden)| if hidden { 0 } else { byte })
+                        .collect(),
+                    hidden: hidden.clone(),
+                });
+            }
+            Claim::ChunkedSubstring { .. } => {
+                return Err(ZkpError::InvalidPublicInput(
+                    "Chunked substring claims are proven per chunk by ChunkedStarkProver"
+                        .to_string(),
+                ));
             }
         }
-
-        Ok(hasher.finalize().to_vec())
-    }
-
-    /// Basic proof validation (simplified)
-    fn basic_proof_validation(&self, proof_data: &StarkProofData) -> bool {
-        // Basic sanity checks
-        proof_data.trace_commitment.len() == 32  // SHA-256 hash length
-            && proof_data.fri_proof.len() == 32  // Our simplified FRI proof length
-            && !proof_data.public_inputs.substring.is_empty()
-            && proof_data.public_inputs.commitment != [0u8; 32]
+        Ok(())
     }
 }
 
-/// Serializable STARK proof data structure
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
-struct StarkProofData {
-    trace_commitment: Vec<u8>,
-    public_inputs: PublicInputs,
-    fri_proof: Vec<u8>,
-    circuit_config: CircuitConfig,
+/// Make the plaintext length public, rejecting a length the circuit cannot hold (it would
+/// alias another length once reduced into the field) and a second claim that discloses another
+/// length
+fn disclose_length(
+    public_inputs: &mut PublicInputs,
+    length: Option<usize>,
+    max_text_len: usize,
+) -> Result<(), ZkpError> {
+    if let Some(length) = length {
+        if length > max_text_len {
+            return Err(ZkpError::InvalidPublicInput(format!(
+                "Public length {} exceeds the maximum plaintext length {}",
+                length, max_text_len
+            )));
+        }
+        if public_inputs.length.is_some_and(|other| other != length) {
+            return Err(ZkpError::InvalidPublicInput(
+                "Conflicting public plaintext lengths".to_string(),
+            ));
+        }
+        public_inputs.length = Some(length);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use p3_field::PrimeField64;
+
     use super::*;
     use crate::core::types::{Claim, Commitment};
 
@@ -306,14 +703,17 @@ mod tests {
             claim: Claim::Substring { value: substring },
         };
 
-        let witness = Witness { plaintext };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
 
         // Generate proof
-        let proof = prover.prove(&statement, &witness);
+        let proof = prover.prove(&statement, &witness).unwrap();
 
         // Verify proof
-        let is_valid = prover.verify(&statement, &proof);
-        assert!(is_valid, "Proof should be valid");
+        let result = prover.verify(&statement, &proof);
+        assert!(result.is_ok(), "Proof should be valid: {:?}", result);
     }
 
     #[test]
@@ -341,16 +741,16 @@ mod tests {
             claim: Claim::Substring { value: substring },
         };
 
-        let witness = Witness { plaintext };
-
-        // Generate proof
-        let proof = prover.prove(&statement, &witness);
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
 
-        // Verify proof should fail
-        let is_valid = prover.verify(&statement, &proof);
+        // Proving must fail instead of producing a proof
+        let result = prover.prove(&statement, &witness);
         assert!(
-            !is_valid,
-            "Proof should be invalid for non-matching substring"
+            matches!(result, Err(ZkpError::InvalidWitness(_))),
+            "Proving should fail for non-matching substring"
         );
     }
 
@@ -365,7 +765,10 @@ mod tests {
         let prover = StarkProver::new(config);
 
         let plaintext = b"hello world!".to_vec();
-        let witness = Witness { plaintext };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
 
         let statement = Statement {
             commitment: Commitment { inner: vec![0; 32] },
@@ -374,7 +777,995 @@ mod tests {
             },
         };
 
-        let offset = prover.extract_offset(&witness, &statement).unwrap();
-        assert_eq!(offset, 6); // "world" starts at position 6
+        let public_inputs = prover.extract_public_inputs(&statement).unwrap();
+        let offsets = prover.extract_offsets(&witness, &public_inputs).unwrap();
+        assert_eq!(offsets, vec![6]); // "world" starts at position 6
+    }
+
+    #[test]
+    fn test_forged_proof_rejected() {
+        let config = CircuitConfig {
+            max_text_len: 32,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let statement = Statement {
+            commitment: Commitment {
+                inner: Sha256::digest(b"hello world!").to_vec(),
+            },
+            claim: Claim::Substring {
+                value: "hello".to_string(),
+            },
+        };
+
+        // A hand-written proof in the old simulated format must not verify
+        let forged = serde_json::json!({
+            "trace_commitment": vec![1u8; 32],
+            "public_inputs": { "commitment": vec![1u8; 32], "substring": b"hello" },
+            "fri_proof": vec![2u8; 32],
+        });
+        let proof = Proof {
+            inner: serde_json::to_vec(&forged).unwrap(),
+        };
+
+        assert!(matches!(
+            prover.verify(&statement, &proof),
+            Err(ZkpError::UnsupportedProofFormat(_))
+        ));
+
+        // A well-formed envelope around garbage must fail to decode
+        let envelope = ProofEnvelope::new(
+            BackendId::Plonky3UniStark,
+            FieldId::Goldilocks,
+            ClaimKind::Substring,
+            prover.config_hash().unwrap(),
+            vec![0xff; 64],
+        );
+        let proof = Proof {
+            inner: envelope.to_bytes().unwrap(),
+        };
+        assert!(matches!(
+            prover.verify(&statement, &proof),
+            Err(ZkpError::SerializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_proof_rejected_for_other_statement() {
+        let config = CircuitConfig {
+            max_text_len: 32,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"hello world!".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+
+        let statement = Statement {
+            commitment: commitment.clone(),
+            claim: Claim::Substring {
+                value: "hello".to_string(),
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+        let proof = prover.prove(&statement, &witness).unwrap();
+        assert!(prover.verify(&statement, &proof).is_ok());
+
+        let other = Statement {
+            commitment,
+            claim: Claim::Substring {
+                value: "world".to_string(),
+            },
+        };
+        assert!(matches!(
+            prover.verify(&other, &proof),
+            Err(ZkpError::ProofVerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_proof_rejected_under_other_config() {
+        let config = CircuitConfig {
+            max_text_len: 32,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+        let prover = StarkProver::new(config.clone());
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"hello world!".to_vec();
+        let statement = Statement {
+            commitment: Commitment {
+                inner: Sha256::digest(&plaintext).to_vec(),
+            },
+            claim: Claim::Substring {
+                value: "hello".to_string(),
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+        let proof = prover.prove(&statement, &witness).unwrap();
+
+        let envelope = ProofEnvelope::from_bytes(&proof.inner).unwrap();
+        assert_eq!(envelope.backend, BackendId::Plonky3UniStark);
+        assert_eq!(envelope.claim, ClaimKind::Substring);
+
+        let other = StarkProver::new(CircuitConfig {
+            max_substring_len: 8,
+            ..config
+        });
+        assert!(matches!(
+            other.verify(&statement, &proof),
+            Err(ZkpError::UnsupportedProofFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_transcript_bound_to_statement() {
+        let config = CircuitConfig {
+            max_text_len: 32,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"hello world!".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = Statement {
+            commitment: commitment.clone(),
+            claim: Claim::Substring {
+                value: "hello".to_string(),
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+        let proof = prover.prove(&statement, &witness).unwrap();
+        let payload = ProofEnvelope::from_bytes(&proof.inner).unwrap().payload;
+
+        // Same public values, but the transcript is seeded for another statement
+        let other = Statement {
+            commitment,
+            claim: Claim::Substring {
+                value: "world".to_string(),
+            },
+        };
+        let air = SubstringAIR::new(prover.config.clone(), TraceLayout::new(&prover.config));
+        let public_inputs = prover.extract_public_inputs(&statement).unwrap();
+        assert!(
+            prover
+                .verify_stark_proof(
+                    &prover.transcript_seed(&statement).unwrap(),
+                    &air,
+                    &payload,
+                    &public_inputs
+                )
+                .is_ok()
+        );
+        assert!(matches!(
+            prover.verify_stark_proof(
+                &prover.transcript_seed(&other).unwrap(),
+                &air,
+                &payload,
+                &public_inputs
+            ),
+            Err(ZkpError::ProofVerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_hiding_proofs_indistinguishable() {
+        let config = CircuitConfig {
+            max_text_len: 32,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+        let prover = StarkProver::new_hiding(config.clone());
+
+        use sha2::{Digest, Sha256};
+        let prove = |plaintext: &[u8]| {
+            let statement = Statement {
+                commitment: Commitment {
+                    inner: Sha256::digest(plaintext).to_vec(),
+                },
+                claim: Claim::Substring {
+                    value: "hello".to_string(),
+                },
+            };
+            let proof = prover
+                .prove(
+                    &statement,
+                    &Witness {
+                        plaintext: plaintext.to_vec(),
+                        salt: Vec::new(),
+                    },
+                )
+                .unwrap();
+            assert!(prover.verify(&statement, &proof).is_ok());
+            (statement, proof)
+        };
+
+        // Different lengths and offsets, same claim: the proofs have the same shape
+        let (statement, first) = prove(b"hello world!");
+        let (_, second) = prove(b"we all say hello to you");
+        assert_eq!(first.inner.len(), second.inner.len());
+        let first_envelope = ProofEnvelope::from_bytes(&first.inner).unwrap();
+        let second_envelope = ProofEnvelope::from_bytes(&second.inner).unwrap();
+        assert_eq!(first_envelope.backend, BackendId::Plonky3UniStarkHiding);
+        assert_eq!(first_envelope.config_hash, second_envelope.config_hash);
+        assert_eq!(first_envelope.payload.len(), second_envelope.payload.len());
+
+        // Fresh randomness every time: re-proving the same statement gives a new proof
+        let (_, again) = prove(b"hello world!");
+        assert_ne!(first.inner, again.inner);
+
+        // Hiding proofs are rejected by a non-hiding verifier with a clear error
+        assert!(matches!(
+            StarkProver::new(config).verify(&statement, &first),
+            Err(ZkpError::UnsupportedProofFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_anchored_substring() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"lab report: hello world ... platelet 50".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let claim = |target: &str, length| Claim::AnchoredSubstring {
+            anchor: "hello world".to_string(),
+            target: target.to_string(),
+            length,
+        };
+
+        let statement = Statement {
+            commitment: commitment.clone(),
+            claim: claim("platelet 50", Some(plaintext.len())),
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+        let proof = prover.prove(&statement, &witness).unwrap();
+        assert!(prover.verify(&statement, &proof).is_ok());
+
+        // The proof does not transfer to another target or another length
+        for other in [
+            claim("platelet 90", Some(witness.plaintext.len())),
+            claim("platelet 50", Some(witness.plaintext.len() + 1)),
+            claim("platelet 50", None),
+        ] {
+            let other = Statement {
+                commitment: commitment.clone(),
+                claim: other,
+            };
+            assert!(prover.verify(&other, &proof).is_err());
+        }
+
+        // A length beyond the circuit is rejected instead of aliasing the real one in the field
+        let aliased = Statement {
+            commitment: commitment.clone(),
+            claim: claim(
+                "platelet 50",
+                Some(witness.plaintext.len() + Goldilocks::ORDER_U64 as usize),
+            ),
+        };
+        assert!(matches!(
+            prover.verify(&aliased, &proof),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+
+        // A wrong public length cannot be proven
+        let wrong_length = Statement {
+            commitment,
+            claim: claim("platelet 50", Some(10)),
+        };
+        assert!(matches!(
+            prover.prove(&wrong_length, &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+    }
+
+    #[test]
+    fn test_range_claim() {
+        let config = CircuitConfig {
+            max_text_len: 32,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"salary: 8500 USD".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |min, max| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::Range {
+                label: "salary: ".to_string(),
+                min,
+                max,
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        let proof = prover.prove(&statement(5000, 10000), &witness).unwrap();
+        assert!(prover.verify(&statement(5000, 10000), &proof).is_ok());
+        assert!(prover.verify(&statement(9000, 10000), &proof).is_err());
+
+        // A value outside the range cannot be proven
+        assert!(matches!(
+            prover.prove(&statement(9000, 10000), &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+
+        // Bounds must fit the comparison gadget
+        assert!(matches!(
+            prover.prove(&statement(0, u64::MAX), &witness),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_regex_claim() {
+        let config = CircuitConfig {
+            max_text_len: 48,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"reach me at carol.d@example.com".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |pattern: &str| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::Regex {
+                pattern: pattern.to_string(),
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        let email = r"[a-z0-9._%+-]+@example\.com";
+        let proof = prover.prove(&statement(email), &witness).unwrap();
+        assert!(prover.verify(&statement(email), &proof).is_ok());
+        assert!(
+            prover
+                .verify(&statement(r"[a-z0-9._%+-]+@example\.org"), &proof)
+                .is_err()
+        );
+
+        // A non-matching plaintext cannot be proven
+        assert!(matches!(
+            prover.prove(&statement(r"^\d+$"), &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+
+        // Unsupported syntax is rejected as a public input error
+        assert!(matches!(
+            prover.prove(&statement("(?=x)"), &witness),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_not_contains_claim() {
+        let config = CircuitConfig {
+            max_text_len: 32,
+            max_substring_len: 16,
+            enable_multi_block_sha: false,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"account 7741 cleared".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |value: &str| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::NotContains {
+                value: value.to_string(),
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        let proof = prover.prove(&statement("7742"), &witness).unwrap();
+        assert!(prover.verify(&statement("7742"), &proof).is_ok());
+        assert!(prover.verify(&statement("7741"), &proof).is_err());
+
+        // A string that is present cannot be proven absent, and neither can the empty string
+        for present in ["7741", "cleared", ""] {
+            assert!(matches!(
+                prover.prove(&statement(present), &witness),
+                Err(ZkpError::InvalidWitness(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_all_claims_in_one_proof() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"name: Alice; age: 34; city: Paris".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let claims = vec![
+            Claim::Substring {
+                value: "Alice".to_string(),
+            },
+            Claim::Substring {
+                value: "Paris".to_string(),
+            },
+            Claim::Range {
+                label: "age: ".to_string(),
+                min: 18,
+                max: 65,
+            },
+            Claim::NotContains {
+                value: "Bob".to_string(),
+            },
+        ];
+        let statement = Statement {
+            commitment: commitment.clone(),
+            claim: Claim::All(claims.clone()),
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        let proof = prover.prove(&statement, &witness).unwrap();
+        assert!(prover.verify(&statement, &proof).is_ok());
+
+        // The proof covers exactly this list of claims
+        let mut fewer = claims.clone();
+        fewer.pop();
+        let mut other = claims;
+        other[1] = Claim::Substring {
+            value: "Rome".to_string(),
+        };
+        for claims in [fewer, other.clone()] {
+            let statement = Statement {
+                commitment: commitment.clone(),
+                claim: Claim::All(claims),
+            };
+            assert!(prover.verify(&statement, &proof).is_err());
+        }
+
+        // Every sub-claim must hold
+        let statement = Statement {
+            commitment: commitment.clone(),
+            claim: Claim::All(other),
+        };
+        assert!(matches!(
+            prover.prove(&statement, &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+
+        // An empty list would only prove knowledge of the preimage
+        for claims in [vec![], vec![Claim::All(vec![])]] {
+            let statement = Statement {
+                commitment: commitment.clone(),
+                claim: Claim::All(claims),
+            };
+            assert!(matches!(
+                prover.prove(&statement, &witness),
+                Err(ZkpError::InvalidPublicInput(_))
+            ));
+            assert!(matches!(
+                prover.verify(&statement, &proof),
+                Err(ZkpError::InvalidPublicInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_ordered_substrings() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"Name: Alice; Name: Bob; clause X; clause Y".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |values: &[&str]| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::Ordered {
+                values: values.iter().map(|value| value.to_string()).collect(),
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        // "Name:" also occurs after "Alice"
+        let proof = prover
+            .prove(&statement(&["Alice", "Name:", "clause X"]), &witness)
+            .unwrap();
+        assert!(
+            prover
+                .verify(&statement(&["Alice", "Name:", "clause X"]), &proof)
+                .is_ok()
+        );
+        assert!(
+            prover
+                .verify(&statement(&["Name:", "Alice", "clause X"]), &proof)
+                .is_err()
+        );
+
+        // Reversed or overlapping values cannot be proven
+        for values in [&["clause Y", "clause X"][..], &["Name: Bob", "Bob;"]] {
+            assert!(matches!(
+                prover.prove(&statement(values), &witness),
+                Err(ZkpError::InvalidWitness(_))
+            ));
+        }
+        assert!(matches!(
+            prover.prove(&statement(&["Alice"]), &witness),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_public_offset_substring() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"hello? report: hello world".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |offset| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::SubstringAt {
+                value: "hello".to_string(),
+                offset,
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        let proof = prover.prove(&statement(15), &witness).unwrap();
+        assert!(prover.verify(&statement(15), &proof).is_ok());
+
+        // The proof is bound to the position, not just to the substring
+        for offset in [0, 14, 16] {
+            assert!(prover.verify(&statement(offset), &proof).is_err());
+        }
+        assert!(matches!(
+            prover.prove(&statement(14), &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+
+        // Offsets past the circuit are rejected instead of aliasing 15 in the field
+        for offset in [15 + Goldilocks::ORDER_U64 as usize, 60, usize::MAX] {
+            assert!(matches!(
+                prover.verify(&statement(offset), &proof),
+                Err(ZkpError::InvalidPublicInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_non_utf8_plaintext_and_chosen_occurrence() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"\xff\xfe id=7; \xc3 id=7; id=9".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |claim| Statement {
+            commitment: commitment.clone(),
+            claim,
+        };
+        let substring = || {
+            statement(Claim::Substring {
+                value: "id=7".to_string(),
+            })
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        // Offsets are byte offsets even when the plaintext is not UTF-8
+        let public_inputs = prover.extract_public_inputs(&substring()).unwrap();
+        assert_eq!(
+            prover.extract_offsets(&witness, &public_inputs).unwrap(),
+            vec![3]
+        );
+        let proof = prover.prove(&substring(), &witness).unwrap();
+        assert!(prover.verify(&substring(), &proof).is_ok());
+
+        // Choose the second occurrence explicitly
+        let proof = prover
+            .prove_with_offsets(&substring(), &witness, vec![11])
+            .unwrap();
+        assert!(prover.verify(&substring(), &proof).is_ok());
+        assert!(
+            prover
+                .prove_with_offsets(&substring(), &witness, vec![12])
+                .is_err()
+        );
+
+        // The occurrence number is proven in-circuit
+        let nth = |n| {
+            statement(Claim::NthOccurrence {
+                value: "id=7".to_string(),
+                n,
+            })
+        };
+        let proof = prover.prove(&nth(2), &witness).unwrap();
+        assert!(prover.verify(&nth(2), &proof).is_ok());
+        assert!(prover.verify(&nth(1), &proof).is_err());
+        assert!(matches!(
+            prover.prove_with_offsets(&nth(2), &witness, vec![3]),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+        assert!(matches!(
+            prover.prove(&nth(3), &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+
+        // Occurrence numbers past the circuit are rejected instead of aliasing 2 in the field
+        assert!(matches!(
+            prover.verify(&nth(2 + Goldilocks::ORDER_U64 as usize), &proof),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+
+        // Caller offsets that overflow are rejected rather than panicking
+        assert!(matches!(
+            prover.prove_with_offsets(&substring(), &witness, vec![usize::MAX]),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+    }
+
+    #[test]
+    fn test_occurrence_count_claim() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"ERROR disk\nWARN fan\nERROR net\nERROR disk".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |value: &str, min, max| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::Count {
+                value: value.to_string(),
+                min,
+                max,
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        // Exactly three errors
+        let proof = prover.prove(&statement("ERROR", 3, 3), &witness).unwrap();
+        assert!(prover.verify(&statement("ERROR", 3, 3), &proof).is_ok());
+        for (min, max) in [(2, 2), (4, 4), (0, 3)] {
+            assert!(
+                prover
+                    .verify(&statement("ERROR", min, max), &proof)
+                    .is_err()
+            );
+        }
+
+        // Public bounds, including "never occurs"
+        for (value, min, max) in [("ERROR", 1, 5), ("FATAL", 0, 0)] {
+            let proof = prover.prove(&statement(value, min, max), &witness).unwrap();
+            assert!(prover.verify(&statement(value, min, max), &proof).is_ok());
+        }
+
+        assert!(matches!(
+            prover.prove(&statement("ERROR", 4, 9), &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+        assert!(matches!(
+            prover.prove(&statement("ERROR", 3, 2), &witness),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_prefix_and_suffix_claims() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"%PDF-1.7 body %PDF-1.7 -- signed".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |claim| Statement {
+            commitment: commitment.clone(),
+            claim,
+        };
+        let prefix = |value: &str, length| {
+            statement(Claim::Prefix {
+                value: value.to_string(),
+                length,
+            })
+        };
+        let suffix = |value: &str, length| {
+            statement(Claim::Suffix {
+                value: value.to_string(),
+                length,
+            })
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+        let len = witness.plaintext.len();
+
+        for (proven, other) in [
+            (
+                prefix("%PDF-1.7", Some(len)),
+                prefix("%PDF-1.7", Some(len + 1)),
+            ),
+            (prefix("%PDF-1.7", None), suffix("%PDF-1.7", None)),
+            (suffix("signed", Some(len)), suffix("signed", None)),
+        ] {
+            let proof = prover.prove(&proven, &witness).unwrap();
+            assert!(prover.verify(&proven, &proof).is_ok());
+            assert!(prover.verify(&other, &proof).is_err());
+        }
+
+        for wrong in [
+            prefix("body", None),
+            suffix("%PDF-1.7", None),
+            prefix("%PDF", Some(7)),
+        ] {
+            assert!(matches!(
+                prover.prove(&wrong, &witness),
+                Err(ZkpError::InvalidWitness(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_normalized_substring_claim() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+
+        use crate::circuits::normalize::Normalization;
+        let plaintext = "年龄　４２; STATUS=Error".as_bytes().to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |value: &str, case_fold, full_width| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::NormalizedSubstring {
+                value: value.to_string(),
+                normalization: Normalization {
+                    case_fold,
+                    full_width,
+                },
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        for proven in [
+            statement("年龄 42", false, true),
+            statement("年龄　42", false, true),
+            statement("status=error", true, false),
+            statement("Ｓtatus=ERROR", true, true),
+        ] {
+            let proof = prover.prove(&proven, &witness).unwrap();
+            assert!(prover.verify(&proven, &proof).is_ok());
+        }
+
+        // A proof does not carry over to other normalization rules
+        let proof = prover
+            .prove(&statement("年龄 42", true, true), &witness)
+            .unwrap();
+        assert!(
+            prover
+                .verify(&statement("年龄 42", false, true), &proof)
+                .is_err()
+        );
+
+        for wrong in [
+            statement("年龄 42", true, false),
+            statement("年龄 43", false, true),
+            statement("status=error", false, true),
+        ] {
+            assert!(matches!(
+                prover.prove(&wrong, &witness),
+                Err(ZkpError::InvalidWitness(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_masked_substring_claim() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config);
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"name: alice, ID: 8731-1234".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |value: &str, hidden: Vec<bool>| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::MaskedSubstring {
+                value: value.to_string(),
+                hidden,
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+        let id_mask: Vec<bool> = "ID: ????-1234".chars().map(|c| c == '?').collect();
+
+        let proven = statement("ID: ????-1234", id_mask.clone());
+        let proof = prover.prove(&proven, &witness).unwrap();
+        assert!(prover.verify(&proven, &proof).is_ok());
+
+        // Hiding more bytes is a different statement
+        let mut wider = id_mask.clone();
+        wider[9] = true;
+        assert!(
+            prover
+                .verify(&statement("ID: ????-?234", wider), &proof)
+                .is_err()
+        );
+
+        assert!(matches!(
+            prover.prove(&statement("ID: ????-9999", id_mask), &witness),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+        assert!(matches!(
+            prover.prove(&statement("ID: ????", vec![true; 2]), &witness),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_redacted_document_claim() {
+        let config = CircuitConfig {
+            max_text_len: 64,
+            max_substring_len: 16,
+            enable_multi_block_sha: true,
+        };
+        let prover = StarkProver::new(config.clone());
+
+        use sha2::{Digest, Sha256};
+        let plaintext = b"Patient: Alice Smith, SSN 123-45-6789, stable".to_vec();
+        let commitment = Commitment {
+            inner: Sha256::digest(&plaintext).to_vec(),
+        };
+        let statement = |document: &[u8]| Statement {
+            commitment: commitment.clone(),
+            claim: Claim::Redacted {
+                document: document.to_vec(),
+                hidden: document.iter().map(|&byte| byte == b'#').collect(),
+            },
+        };
+        let witness = Witness {
+            plaintext,
+            salt: Vec::new(),
+        };
+
+        // The document is longer than max_substring_len: the mask covers the whole plaintext
+        let redacted = b"Patient: ##### #####, SSN ###########, stable";
+        let proof = prover.prove(&statement(redacted), &witness).unwrap();
+        assert!(prover.verify(&statement(redacted), &proof).is_ok());
+
+        // Every other byte of the original is visible: a changed or shifted byte is rejected
+        for wrong in [
+            &b"Patient: ##### #####, SSN ###########, stab1e"[..],
+            b"Patient: ##### #####, SSN ###########, stable.",
+            b"Patient: ##### #####, SSN ###########,  stabl",
+        ] {
+            assert!(prover.verify(&statement(wrong), &proof).is_err());
+            assert!(prover.prove(&statement(wrong), &witness).is_err());
+        }
+
+        // Documents longer than the plaintext limit or with too many redactions are rejected
+        let long = vec![b'#'; config.max_text_len + 1];
+        assert!(matches!(
+            prover.prove(&statement(&long), &witness),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+        let striped: Vec<u8> = (0 .. 2 * MAX_REDACTION_BOUNDARIES)
+            .map(|k| if k % 2 == 0 { b'#' } else { b'x' })
+            .collect();
+        assert!(matches!(
+            prover.prove(&statement(&striped), &witness),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
     }
 }
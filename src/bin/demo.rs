@@ -34,20 +34,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let commitment = hasher.finalize().into();
 
     println!("🌍 公开输入（验证者可见）:");
-    println!("  - 承诺（SHA-256）: {}", hex::encode(&commitment));
+    println!("  - 承诺（SHA-256）: {}", hex::encode(commitment));
     println!("  - 公开子串: {:?}", String::from_utf8_lossy(&substring));
     println!();
 
     // 4. 创建电路参数
     let params = CircuitParams {
         config: config.clone(),
-        public_inputs: PublicInputs {
-            commitment,
-            substring,
-        },
+        public_inputs: PublicInputs::substring(commitment, substring),
         witness: Some(CircuitWitness {
             plaintext,
-            offset,
+            offsets: vec![offset],
         }),
     };
 
@@ -70,7 +67,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let layout = trace_generator.get_layout();
     let air = SubstringAIR::new(config, layout.clone());
     
-    let air_valid = air.verify_all_constraints::<Goldilocks>(&trace, &params.public_inputs)?;
+    let air_valid = air
+        .verify_all_constraints::<Goldilocks>(&trace, &params.public_inputs)
+        .is_ok();
     println!("    AIR 约束: {}", if air_valid { "✅ 通过" } else { "❌ 失败" });
     println!("    约束数量: {}", air.num_constraints());
 
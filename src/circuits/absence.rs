@@ -0,0 +1,63 @@
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+use crate::{
+    circuits::{shift::MismatchLayout, trace::TraceLayout},
+    error::ZkpError,
+};
+
+/// 子串不出现（否定子串）子电路的列布局
+///
+/// 每一行（每个候选起点）都给出一个失配见证（见 [`MismatchLayout`]），
+/// 证明从该行开始的窗口不等于 `t`
+#[derive(Debug, Clone)]
+pub struct AbsenceLayout {
+    /// 移位列与失配见证
+    pub mismatch: MismatchLayout,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+impl AbsenceLayout {
+    /// 从第 `start` 列开始依次分配子电路的列
+    pub fn new(start: usize, max_substring_len: usize) -> Self {
+        let mismatch = MismatchLayout::new(start, max_substring_len);
+
+        Self {
+            end: mismatch.end,
+            mismatch,
+        }
+    }
+
+    /// 填充子电路的 trace 列，原文中出现 `absent` 时返回错误
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        plaintext: &[u8],
+        absent: &[u8],
+    ) -> Result<(), ZkpError> {
+        let matches = self.mismatch.generate_trace(trace, plaintext, absent);
+        if matches.contains(&true) {
+            return Err(ZkpError::InvalidWitness(
+                "Plaintext contains the absent string".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 子串不出现约束：每一行都失配
+    ///
+    /// `absent_pv` 为公开值中该字符串的 `[|t|, t[0..max], mask[0..max]]`
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+        absent_pv: &[AB::Expr],
+    ) {
+        self.mismatch
+            .eval(builder, local, next, layout, absent_pv, AB::Expr::ONE);
+    }
+}
@@ -1,11 +1,33 @@
-use p3_field::{Field, integers::QuotientMap};
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{Field, PrimeCharacteristicRing, integers::QuotientMap};
+use p3_goldilocks::Goldilocks;
+use p3_matrix::{
+    Matrix,
+    dense::{RowMajorMatrix, RowMajorMatrixView},
+    stack::VerticalPair,
+};
 
 use crate::{
-    circuits::trace::TraceLayout,
+    circuits::{
+        bits::pack,
+        normalize::NormalizerLayout,
+        redaction,
+        trace::{TraceLayout, to_row_major},
+    },
     config::{CircuitConfig, PublicInputs},
     error::ZkpError,
+    hash::sha256,
 };
 
+/// 公开值中承诺字（8个大端32位字）的范围
+const COMMITMENT_PV: std::ops::Range<usize> = 0 .. 8;
+/// 公开值中“是否公开原文长度”标志的下标
+const LENGTH_FLAG_PV: usize = 8;
+/// 公开值中原文长度的下标
+const LENGTH_PV: usize = 9;
+/// 公开值中第一个匹配窗口的起始下标
+const WINDOWS_PV_START: usize = 10;
+
 /// AIR 约束系统定义
 ///
 /// 实现子串包含证明的所有代数约束：
@@ -13,279 +35,890 @@ use crate::{
 /// 2. 子串匹配约束
 /// 3. 范围检查约束
 /// 4. 逻辑一致性约束
+/// 5. 子串不出现约束（否定子串命题）
+/// 6. 数值区间约束（区间命题）
+/// 7. 正则匹配约束（正则命题）
+/// 8. 窗口先后约束（有序子串命题）
+/// 9. 窗口起点约束（公开偏移子串命题）
+/// 10. 第 n 次出现约束（第 n 次出现命题）
+/// 11. 出现次数约束（出现次数命题）
+/// 12. 首尾锚定约束（前缀与后缀命题）
+/// 13. 规范化约束（规范化子串命题）
+/// 14. 涂黑文档约束（涂黑文档命题）
+///
+/// 掩码子串命题不单独成类：被隐藏的字节只是不参与第2类的逐字节比较。
+///
+/// 约束通过 [`Air::eval`] 以符号形式表达在相邻两行 `(local, next)` 上，
+/// 同一份定义同时用于证明生成、证明验证以及 [`SubstringAIR::verify_all_constraints`] 调试检查。
+/// 匹配窗口的个数由列布局决定，所有窗口共享同一个原文列与同一次 SHA-256 计算。
 #[derive(Debug, Clone)]
 pub struct SubstringAIR {
     layout: TraceLayout,
     config: CircuitConfig,
 }
 
+/// 一个匹配窗口在公开值中的位置
+struct WindowPv {
+    /// 子串长度 |s|
+    len: usize,
+    /// 子串字节 s[0..max_substring_len]
+    bytes: usize,
+    /// 子串掩码 mask[0..max_substring_len]（`j < |s|` 时为1）
+    mask: usize,
+}
+
 impl SubstringAIR {
     pub fn new(config: CircuitConfig, layout: TraceLayout) -> Self {
         Self { layout, config }
     }
 
-    /// 获取约束的总数
-    pub fn num_constraints(&self) -> usize {
-        self.num_sha256_constraints()
-            + self.num_substring_constraints()
-            + self.num_range_constraints()
-            + self.num_logic_constraints()
+    /// 公开值的个数
+    pub fn num_public_values(&self) -> usize {
+        self.redaction_pv(self.layout.redactions.len())
     }
 
-    /// SHA-256 约束数量
-    fn num_sha256_constraints(&self) -> usize {
-        // 简化版本：32个约束用于最终哈希比较
-        // 完整版本需要：64轮 * 每轮多个约束 + 消息调度约束
-        32
+    /// 第 `i` 个区间上下界在公开值中的起始下标（紧跟在所有窗口、不出现串与被计数串之后）
+    fn range_pv(&self, i: usize) -> usize {
+        let strings =
+            self.layout.windows.len() + self.layout.absent.len() + self.layout.counts.len();
+        WINDOWS_PV_START + strings * self.window_pv_width() + 2 * i
     }
 
-    /// 子串匹配约束数量
-    fn num_substring_constraints(&self) -> usize {
-        // 每个可能的位置一个匹配约束
-        self.config.max_text_len
+    /// 第 `i` 个公开起点在公开值中的下标（紧跟在所有区间上下界之后）
+    fn position_pv(&self, i: usize) -> usize {
+        self.range_pv(self.layout.numbers.len()) + i
     }
 
-    /// 范围检查约束数量
-    fn num_range_constraints(&self) -> usize {
-        // 每个字节一个范围约束
-        self.config.max_text_len
+    /// 第 `i` 个出现序号在公开值中的下标（紧跟在所有公开起点之后）
+    fn nth_pv(&self, i: usize) -> usize {
+        self.position_pv(self.layout.positions.len()) + i
     }
 
-    /// 逻辑一致性约束数量
-    fn num_logic_constraints(&self) -> usize {
-        // 偏移指示器和匹配标志的逻辑约束
-        self.config.max_text_len * 2
+    /// 第 `i` 个出现次数上下界在公开值中的起始下标（紧跟在所有出现序号之后）
+    fn count_pv(&self, i: usize) -> usize {
+        self.nth_pv(self.layout.nth_occurrences.len()) + 2 * i
     }
 
-    /// 评估所有约束
-    pub fn evaluate_constraints<F: Field + QuotientMap<u8> + QuotientMap<usize>>(
-        &self,
-        trace: &[Vec<F>],
-        public_inputs: &PublicInputs,
-    ) -> Result<Vec<F>, ZkpError> {
-        let mut constraints = Vec::new();
-
-        // 1. SHA-256 约束
-        constraints.extend(self.evaluate_sha256_constraints(trace, public_inputs)?);
+    /// 第 `w` 个窗口的规范化子电路（窗口与规范化后的原文比较时）
+    fn normalizer(&self, w: usize) -> Option<&NormalizerLayout> {
+        self.layout
+            .normalizers
+            .iter()
+            .find(|normalizer| normalizer.window == w)
+    }
 
-        // 2. 子串匹配约束
-        constraints.extend(self.evaluate_substring_constraints(trace, public_inputs)?);
+    /// 第 `i` 个掩码窗口的隐藏标志在公开值中的起始下标（紧跟在所有次数区间之后）
+    fn masked_pv(&self, i: usize) -> usize {
+        self.count_pv(self.layout.counts.len()) + i * self.config.max_substring_len
+    }
 
-        // 3. 范围检查约束
-        constraints.extend(self.evaluate_range_constraints(trace)?);
+    /// 第 `i` 份涂黑文档在公开值中的起始下标（紧跟在所有隐藏标志之后）
+    fn redaction_pv(&self, i: usize) -> usize {
+        self.masked_pv(self.layout.masked.len())
+            + self.layout.redactions[.. i]
+                .iter()
+                .map(|redaction| redaction.num_public_values())
+                .sum::<usize>()
+    }
 
-        // 4. 逻辑一致性约束
-        constraints.extend(self.evaluate_logic_constraints(trace, public_inputs)?);
+    /// 每个窗口占用的公开值个数
+    fn window_pv_width(&self) -> usize {
+        1 + 2 * self.config.max_substring_len
+    }
 
-        Ok(constraints)
+    /// 第 `w` 个窗口在公开值中的位置
+    fn window_pv(&self, w: usize) -> WindowPv {
+        let len = WINDOWS_PV_START + w * self.window_pv_width();
+        WindowPv {
+            len,
+            bytes: len + 1,
+            mask: len + 1 + self.config.max_substring_len,
+        }
     }
 
-    /// 评估 SHA-256 哈希一致性约束
-    fn evaluate_sha256_constraints<F: Field + QuotientMap<u8>>(
+    /// 构造 STARK 公开值
+    ///
+    /// 布局：`[commitment 的8个大端字, 长度标志, 原文长度, 窗口..., 不出现串...,
+    /// 被计数串..., 区间..., 起点..., 序号..., 次数区间...]`。
+    /// 每个窗口、不出现串与被计数串都为 `[|s|, s[0..max], mask[0..max]]`，
+    /// 其中 `max = max_substring_len`，不足部分补零，`mask[j] = (j < |s|)`，
+    /// 保证同一配置下公开值长度固定。未公开长度时长度标志与长度均为零；
+    /// 每个数值区间追加一对 `(min, max)`，每个公开起点追加一个偏移，
+    /// 每个第 n 次出现命题追加序号 n，每个出现次数命题追加一对 `(min, max)`，
+    /// 每个掩码窗口追加 `hidden[0..max]`（不足部分补零），
+    /// 每份涂黑文档追加其翻转边界、指纹求值点与指纹（见 [`redaction::public_values`]）
+    pub fn public_values<F: Field + QuotientMap<u8> + QuotientMap<usize>>(
         &self,
-        trace: &[Vec<F>],
         public_inputs: &PublicInputs,
-    ) -> Result<Vec<F>, ZkpError> {
-        let mut constraints = Vec::new();
-        let trace_len = trace[0].len();
-
-        // 简化版本：检查最终哈希输出
-        // 约束：trace 中的最终哈希状态 == 公开承诺
-        for (i, &expected_byte) in public_inputs.commitment.iter().enumerate() {
-            if i < self.layout.sha_state_cols.len() && trace_len >= 32 {
-                let col_idx = self.layout.sha_state_cols.start + i;
-                let row_idx = trace_len - 32 + i; // 假设哈希在最后存储
-
-                let actual = trace[col_idx][row_idx.min(trace_len - 1)];
-                let expected = F::from_int(expected_byte);
-
-                // 约束：actual - expected == 0
-                constraints.push(actual - expected);
-            }
+    ) -> Vec<F> {
+        let max_substring_len = self.config.max_substring_len;
+        let mut values = Vec::with_capacity(self.num_public_values());
+        values.extend(
+            sha256::digest_words(&public_inputs.commitment)
+                .iter()
+                .map(|&word| F::from_u32(word)),
+        );
+        values.push(F::from_bool(public_inputs.length.is_some()));
+        values.push(F::from_int(public_inputs.length.unwrap_or(0)));
+        let strings = public_inputs
+            .windows
+            .iter()
+            .chain(&public_inputs.absent)
+            .map(|window| &window.bytes)
+            .chain(public_inputs.counts.iter().map(|count| &count.bytes));
+        for bytes in strings {
+            values.push(F::from_int(bytes.len()));
+            values.extend(
+                (0 .. max_substring_len).map(|i| F::from_int(bytes.get(i).copied().unwrap_or(0))),
+            );
+            values.extend((0 .. max_substring_len).map(|i| F::from_bool(i < bytes.len())));
+        }
+        for range in &public_inputs.ranges {
+            values.push(F::from_u64(range.min));
+            values.push(F::from_u64(range.max));
         }
+        for position in &public_inputs.positions {
+            values.push(F::from_int(position.offset));
+        }
+        for nth in &public_inputs.nth_occurrences {
+            values.push(F::from_int(nth.n));
+        }
+        for count in &public_inputs.counts {
+            values.push(F::from_int(count.min));
+            values.push(F::from_int(count.max));
+        }
+        for masked in &public_inputs.masked {
+            values.extend(
+                (0 .. max_substring_len)
+                    .map(|j| F::from_bool(masked.hidden.get(j).copied().unwrap_or(false))),
+            );
+        }
+        for document in &public_inputs.redacted {
+            values.extend(redaction::public_values::<F>(
+                &public_inputs.commitment,
+                document,
+            ));
+        }
+        values
+    }
 
-        // TODO: 添加完整的 SHA-256 轮函数约束
-        // 这需要实现：
-        // - 消息调度约束：W[t] = σ₁(W[t-2]) + W[t-7] + σ₀(W[t-15]) + W[t-16]
-        // - 压缩函数约束：8个状态寄存器的更新规则
-        // - 每轮的 Ch, Maj, Σ₀, Σ₁ 函数约束
+    /// 获取约束的总数（符号化求值 [`Air::eval`] 得到）
+    pub fn num_constraints(&self) -> usize {
+        p3_uni_stark::get_symbolic_constraints::<Goldilocks, _>(self, 0, self.num_public_values())
+            .len()
+    }
 
-        Ok(constraints)
+    /// SHA-256 哈希一致性约束
+    ///
+    /// 逐轮约束压缩函数与消息调度，最后一行的链接值等于公开承诺
+    fn eval_sha256<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        self.layout
+            .sha256
+            .eval(builder, local, next, &pis[COMMITMENT_PV]);
     }
 
-    /// 评估子串匹配约束  
-    fn evaluate_substring_constraints<F: Field + QuotientMap<u8>>(
+    /// 子串匹配约束
+    ///
+    /// 被比较的原文字节与打包进 SHA-256 消息字的字节逐行绑定，
+    /// 因此每个子串窗口检查的都是承诺所对应的原文
+    fn eval_substring<AB: AirBuilder>(
         &self,
-        trace: &[Vec<F>],
-        public_inputs: &PublicInputs,
-    ) -> Result<Vec<F>, ZkpError> {
-        let mut constraints = Vec::new();
-        let trace_len = trace[0].len();
-
-        // 为公开子串的每个字节创建约束
-        for (_i, &expected_byte) in public_inputs.substring.iter().enumerate() {
-            for row in 0 .. trace_len {
-                // 约束：如果偏移指示器为1且位置匹配，则字节必须相等
-                let offset_indicator = trace[self.layout.offset_indicator_col][row];
-                let plaintext_byte = trace[self.layout.plaintext_col][row];
-                let substring_byte = trace[self.layout.substring_col][row];
-                let expected = F::from_int(expected_byte);
-
-                // 约束1：在匹配窗口内，子串字节必须等于公开值
-                // offset_indicator * (substring_byte - expected) == 0
-                constraints.push(offset_indicator * (substring_byte - expected));
-
-                // 约束2：在匹配窗口内，原文字节必须等于子串字节
-                // offset_indicator * (plaintext_byte - substring_byte) == 0
-                constraints.push(offset_indicator * (plaintext_byte - substring_byte));
+        builder: &mut AB,
+        local: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        let plaintext_byte = local[self.layout.plaintext_col];
+        let sha256 = &self.layout.sha256;
+        let is_data = local[sha256.is_data_col];
+
+        // 原文列就是 SHA-256 消息字节流中的原文部分，填充字节处为零
+        // plaintext_byte == is_data * message_byte
+        builder.assert_eq(plaintext_byte, is_data * local[sha256.byte_col]);
+
+        for (w, window) in self.layout.windows.iter().enumerate() {
+            let offset_indicator = local[window.offset_indicator_col];
+            let substring_byte = local[window.substring_col];
+            let match_flag = local[window.match_flag_col];
+
+            // 子串列取公开子串中由下标选择子指定的字节：第 k+j 行为 s[j]
+            // substring_byte == Σ sel_j * s[j]
+            let selected: AB::Expr = window
+                .substring_sel_cols
+                .clone()
+                .zip(&pis[self.window_pv(w).bytes ..])
+                .map(|(col, s_j)| s_j.clone() * local[col])
+                .sum();
+            builder.assert_eq(substring_byte, selected);
+
+            // 在匹配窗口内，原文字节必须等于子串字节
+            // offset_indicator * (plaintext_byte - substring_byte) == 0
+            // 规范化窗口改为在规则起点行上比较规范化字节，续字节行不参与比较；
+            // 掩码窗口中被隐藏的字节不参与比较
+            if let Some(normalizer) = self.normalizer(w) {
+                builder
+                    .when(offset_indicator * normalizer.emits::<AB>(local))
+                    .assert_eq(local[normalizer.normalized_col], substring_byte);
+            } else if let Some(i) = self.layout.masked.iter().position(|&m| m == w) {
+                // hidden = Σ sel_j * hidden[j]
+                let hidden: AB::Expr = window
+                    .substring_sel_cols
+                    .clone()
+                    .zip(&pis[self.masked_pv(i) ..])
+                    .map(|(col, hidden_j)| hidden_j.clone() * local[col])
+                    .sum();
+                builder
+                    .when(offset_indicator)
+                    .when(AB::Expr::ONE - hidden)
+                    .assert_eq(plaintext_byte, substring_byte);
+            } else {
+                builder
+                    .when(offset_indicator)
+                    .assert_eq(plaintext_byte, substring_byte);
             }
+
+            // 在匹配窗口内，匹配标志必须为1
+            // offset_indicator * (1 - match_flag) == 0
+            builder.when(offset_indicator).assert_one(match_flag);
+
+            // 匹配窗口只能落在原文字节上，不能落在填充或长度字段上
+            builder.when(offset_indicator).assert_one(is_data);
+        }
+    }
+
+    /// 范围检查约束
+    ///
+    /// 消息字节分解为8个布尔位，`byte == Σ bitᵢ·2ⁱ` 保证 byte ∈ [0, 255]；
+    /// 原文列等于原文行上的消息字节，因此同样落在该范围内
+    fn eval_range<AB: AirBuilder>(&self, builder: &mut AB, local: &[AB::Var]) {
+        let bits = &local[self.layout.range_bits_cols.clone()];
+        for &bit in bits {
+            builder.assert_bool(bit);
         }
 
-        Ok(constraints)
+        builder.assert_eq(local[self.layout.sha256.byte_col], pack::<AB>(bits));
     }
 
-    /// 评估范围检查约束
-    fn evaluate_range_constraints<F: Field>(&self, trace: &[Vec<F>]) -> Result<Vec<F>, ZkpError> {
-        let mut constraints = Vec::new();
-        let trace_len = trace[0].len();
+    /// 逻辑一致性约束
+    fn eval_logic<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        // 公开长度时，原文字节数（即 SHA-256 长度字段所编码的长度）等于公开值
+        let data_count = local[self.layout.sha256.data_count_col];
+        builder
+            .when_last_row()
+            .when(pis[LENGTH_FLAG_PV].clone())
+            .assert_eq(data_count, pis[LENGTH_PV].clone());
+
+        for w in 0 .. self.layout.windows.len() {
+            self.eval_window_logic(builder, local, next, pis, w);
+        }
+    }
 
-        // 为每个原文字节添加范围约束：0 ≤ byte ≤ 255
-        for row in 0 .. trace_len {
-            let _byte_val = trace[self.layout.plaintext_col][row];
-            let range_flag = trace[self.layout.range_check_col][row];
+    /// 数值区间约束
+    ///
+    /// 解析各标签窗口之后的十进制数，并与公开的上下界比较
+    fn eval_number<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        for (i, number) in self.layout.numbers.iter().enumerate() {
+            let range_pv = self.range_pv(i);
+            number.eval(
+                builder,
+                local,
+                next,
+                &self.layout,
+                &self.layout.windows[number.window],
+                &pis[range_pv .. range_pv + 2],
+            );
+        }
+    }
 
-            // 简化的范围检查：如果字节非零，则范围标志必须为1
-            // 完整版本需要查表或分解约束
+    /// 单个匹配窗口的逻辑一致性约束
+    ///
+    /// 规范化窗口的每个子串字节对应一个规则起点行及其续字节行：
+    /// 计数只在起点行上累加，续字节行沿用上一行的下标选择子
+    fn eval_window_logic<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+        w: usize,
+    ) {
+        let window = &self.layout.windows[w];
+        let window_pv = self.window_pv(w);
+        let normalizer = self.normalizer(w);
+        let offset_indicator = local[window.offset_indicator_col];
+        let window_count = local[window.window_count_col];
+
+        // 偏移指示器和匹配标志是布尔值
+        builder.assert_bool(offset_indicator);
+        builder.assert_bool(local[window.match_flag_col]);
+
+        // 本行与下一行是否计入窗口（规范化窗口只计规则起点行）
+        let (counted, next_counted): (AB::Expr, AB::Expr) = match normalizer {
+            Some(normalizer) => (
+                offset_indicator * normalizer.emits::<AB>(local),
+                next[window.offset_indicator_col] * normalizer.emits::<AB>(next),
+            ),
+            None => (
+                offset_indicator.into(),
+                next[window.offset_indicator_col].into(),
+            ),
+        };
 
-            // 约束：range_flag * (range_flag - 1) == 0 (布尔约束)
-            constraints.push(range_flag * (range_flag - F::ONE));
+        // 窗口计数器：累计偏移指示器之和
+        builder.when_first_row().assert_eq(window_count, counted);
+        builder
+            .when_transition()
+            .assert_eq(next[window.window_count_col], window_count + next_counted);
 
-            // TODO: 添加完整的范围检查约束
-            // 可以使用查表约束或位分解约束来确保 byte_val ∈ [0, 255]
+        // 窗口大小必须等于子串长度
+        builder
+            .when_last_row()
+            .assert_eq(window_count, pis[window_pv.len].clone());
+
+        // 子串下标选择子：窗口是一段连续区间，第 j 个字节之后一行必为第 j+1 个字节，
+        // 起点 k 由证明者私下选择，不出现在公开值中
+        let sel = |row: &[AB::Var], j: usize| row[window.substring_sel_cols.start + j];
+        let max_substring_len = self.config.max_substring_len;
+        let mask = &pis[window_pv.mask .. window_pv.mask + max_substring_len];
+
+        for j in 0 .. max_substring_len {
+            builder.assert_bool(sel(local, j));
         }
+        let selected: AB::Expr = (0 .. max_substring_len).map(|j| sel(local, j).into()).sum();
+        builder.assert_eq(offset_indicator, selected);
+
+        // 首行只能是窗口起点，之后每个非起点位置都由上一行推出，窗口在 |s| 处结束
+        let Some(normalizer) = normalizer else {
+            for (j, mask_j) in mask.iter().enumerate().skip(1) {
+                builder.when_first_row().assert_zero(sel(local, j));
+                builder
+                    .when_transition()
+                    .assert_eq(sel(next, j), sel(local, j - 1) * mask_j.clone());
+            }
+            return;
+        };
 
-        Ok(constraints)
+        // 规范化窗口：续字节行的选择子与上一行相同，其余行按上式推进
+        let next_continues = normalizer.continues::<AB>(next);
+        for j in 0 .. max_substring_len {
+            builder
+                .when_transition()
+                .when(next_continues.clone())
+                .assert_eq(sel(next, j), sel(local, j));
+        }
+        for (j, mask_j) in mask.iter().enumerate().skip(1) {
+            builder.when_first_row().assert_zero(sel(local, j));
+            builder
+                .when_transition()
+                .when(AB::Expr::ONE - next_continues.clone())
+                .assert_eq(sel(next, j), sel(local, j - 1) * mask_j.clone());
+        }
     }
 
-    /// 评估逻辑一致性约束
-    fn evaluate_logic_constraints<F: Field + QuotientMap<usize>>(
+    /// 子串不出现约束
+    ///
+    /// 每个候选起点都有一个失配见证，公开值位于所有窗口之后
+    fn eval_absence<AB: AirBuilder>(
         &self,
-        trace: &[Vec<F>],
-        public_inputs: &PublicInputs,
-    ) -> Result<Vec<F>, ZkpError> {
-        let mut constraints = Vec::new();
-        let trace_len = trace[0].len();
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        for (i, absence) in self.layout.absent.iter().enumerate() {
+            let start = self.window_pv(self.layout.windows.len() + i).len;
+            let absent_pv = &pis[start .. start + self.window_pv_width()];
+            absence.eval(builder, local, next, &self.layout, absent_pv);
+        }
+    }
 
-        for row in 0 .. trace_len {
-            let offset_indicator = trace[self.layout.offset_indicator_col][row];
-            let match_flag = trace[self.layout.match_flag_col][row];
+    /// 正则匹配约束
+    ///
+    /// 在原文上逐行走 DFA，最后停在接受状态
+    fn eval_regex<AB: AirBuilder>(&self, builder: &mut AB, local: &[AB::Var], next: &[AB::Var]) {
+        for regex in &self.layout.regexes {
+            regex.eval(builder, local, next, &self.layout);
+        }
+    }
 
-            // 约束1：偏移指示器是布尔值
-            // offset_indicator * (offset_indicator - 1) == 0
-            constraints.push(offset_indicator * (offset_indicator - F::ONE));
+    /// 窗口先后约束
+    ///
+    /// 在后窗口的起点行上，在前窗口于本行之前的计数必须已经达到其长度，
+    /// 即 `offset_before + |before| ≤ offset_after`，两个偏移都不公开
+    fn eval_order<AB: AirBuilder>(&self, builder: &mut AB, local: &[AB::Var], pis: &[AB::Expr]) {
+        for order in &self.layout.orders {
+            let before = &self.layout.windows[order.before];
+            let after = &self.layout.windows[order.after];
+            let counted_before =
+                local[before.window_count_col] - local[before.offset_indicator_col];
+            builder
+                .when(local[after.substring_sel_cols.start])
+                .assert_eq(
+                    counted_before,
+                    pis[self.window_pv(order.before).len].clone(),
+                );
+        }
+    }
 
-            // 约束2：匹配标志是布尔值
-            // match_flag * (match_flag - 1) == 0
-            constraints.push(match_flag * (match_flag - F::ONE));
+    /// 窗口起点约束
+    ///
+    /// 原文从第0行开始逐行排列，原文行上的字节计数比行号大1，
+    /// 因此窗口起点行（`sel_0 = 1`）的计数等于公开偏移加1
+    fn eval_position<AB: AirBuilder>(&self, builder: &mut AB, local: &[AB::Var], pis: &[AB::Expr]) {
+        let data_count = local[self.layout.sha256.data_count_col];
+        for (i, &w) in self.layout.positions.iter().enumerate() {
+            let window = &self.layout.windows[w];
+            builder
+                .when(local[window.substring_sel_cols.start])
+                .assert_eq(data_count, pis[self.position_pv(i)].clone() + AB::Expr::ONE);
+        }
+    }
 
-            // 约束3：如果偏移指示器为1，匹配标志也必须为1
-            // offset_indicator * (1 - match_flag) == 0
-            constraints.push(offset_indicator * (F::ONE - match_flag));
+    /// 第 n 次出现约束
+    ///
+    /// 对窗口的子串逐行计数出现次数，窗口起点行上的计数（含本行）等于公开序号 n
+    fn eval_nth_occurrence<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        for (i, nth) in self.layout.nth_occurrences.iter().enumerate() {
+            let start = self.window_pv(nth.window).len;
+            let occurrence = &nth.occurrence;
+            occurrence.eval(
+                builder,
+                local,
+                next,
+                &self.layout,
+                &pis[start .. start + self.window_pv_width()],
+            );
+
+            let window = &self.layout.windows[nth.window];
+            builder
+                .when(local[window.substring_sel_cols.start])
+                .assert_eq(local[occurrence.count_col], pis[self.nth_pv(i)].clone());
         }
+    }
 
-        // 约束4：确保偏移指示器形成连续的窗口
-        let substring_len = public_inputs.substring.len();
-        let mut window_sum = F::ZERO;
+    /// 出现次数约束
+    ///
+    /// 被计数串的公开值位于窗口与不出现串之后，计数列的最终值落在公开区间内
+    fn eval_count<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        let strings_before = self.layout.windows.len() + self.layout.absent.len();
+        for (i, count) in self.layout.counts.iter().enumerate() {
+            let start = self.window_pv(strings_before + i).len;
+            let count_pv = self.count_pv(i);
+            count.eval(
+                builder,
+                local,
+                next,
+                &self.layout,
+                &pis[start .. start + self.window_pv_width()],
+                &pis[count_pv .. count_pv + 2],
+            );
+        }
+    }
 
-        for row in 0 .. trace_len {
-            window_sum += trace[self.layout.offset_indicator_col][row];
+    /// 首尾锚定约束
+    ///
+    /// 前缀窗口从第0行开始；后缀窗口覆盖最后一个原文行（其下一行不是原文），
+    /// 由于窗口连续且只落在原文行上，它恰好在原文末尾结束
+    fn eval_text_ends<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+    ) {
+        for &w in &self.layout.prefixes {
+            let window = &self.layout.windows[w];
+            builder
+                .when_first_row()
+                .assert_one(local[window.substring_sel_cols.start]);
         }
 
-        // 窗口大小必须等于子串长度
-        constraints.push(window_sum - F::from_int(substring_len));
+        let is_data = local[self.layout.sha256.is_data_col];
+        let next_is_data = next[self.layout.sha256.is_data_col];
+        for &w in &self.layout.suffixes {
+            let window = &self.layout.windows[w];
+            builder
+                .when_transition()
+                .when(is_data * (AB::Expr::ONE - next_is_data))
+                .assert_one(local[window.offset_indicator_col]);
+        }
+    }
 
-        Ok(constraints)
+    /// 规范化约束
+    ///
+    /// 逐行把原文映射为规范化字节，供规范化窗口比较
+    fn eval_normalize<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+    ) {
+        for normalizer in &self.layout.normalizers {
+            normalizer.eval(builder, local, next, &self.layout);
+        }
+    }
+
+    /// 涂黑文档约束
+    ///
+    /// 掩码由公开的翻转边界逐行确定，未涂黑的原文字节与公开文档比较指纹
+    fn eval_redaction<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        pis: &[AB::Expr],
+    ) {
+        for (i, redaction) in self.layout.redactions.iter().enumerate() {
+            let start = self.redaction_pv(i);
+            redaction.eval(
+                builder,
+                local,
+                next,
+                &self.layout,
+                &pis[start .. start + redaction.num_public_values()],
+            );
+        }
     }
 
     /// 验证所有约束是否满足（用于调试和测试）
+    ///
+    /// 在具体 trace 上逐行执行与证明系统相同的 [`Air::eval`]，
+    /// 失败时在错误中返回第一个不满足的约束及其所在行
     pub fn verify_all_constraints<F: Field + QuotientMap<u8> + QuotientMap<usize>>(
         &self,
         trace: &[Vec<F>],
         public_inputs: &PublicInputs,
-    ) -> Result<bool, ZkpError> {
-        let constraints = self.evaluate_constraints(trace, public_inputs)?;
-
-        // 所有约束都必须为0
-        for (i, constraint) in constraints.iter().enumerate() {
-            if *constraint != F::ZERO {
-                println!("Constraint {} failed: {:?}", i, constraint);
-                return Ok(false);
+    ) -> Result<(), ZkpError> {
+        if trace.len() != self.layout.total_columns {
+            return Err(ZkpError::ConstraintNotSatisfied(format!(
+                "Trace width mismatch: {} != {}",
+                trace.len(),
+                self.layout.total_columns
+            )));
+        }
+        if public_inputs.windows.len() != self.layout.windows.len() {
+            return Err(ZkpError::InvalidPublicInput(format!(
+                "Window count mismatch: {} != {}",
+                public_inputs.windows.len(),
+                self.layout.windows.len()
+            )));
+        }
+        if public_inputs.absent.len() != self.layout.absent.len() {
+            return Err(ZkpError::InvalidPublicInput(format!(
+                "Absent string count mismatch: {} != {}",
+                public_inputs.absent.len(),
+                self.layout.absent.len()
+            )));
+        }
+        if !public_inputs
+            .ranges
+            .iter()
+            .map(|range| range.window)
+            .eq(self.layout.numbers.iter().map(|number| number.window))
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Value ranges do not match the trace layout".to_string(),
+            ));
+        }
+        if !public_inputs
+            .dfas
+            .iter()
+            .eq(self.layout.regexes.iter().map(|regex| &regex.dfa))
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Patterns do not match the trace layout".to_string(),
+            ));
+        }
+        if public_inputs.orders != self.layout.orders {
+            return Err(ZkpError::InvalidPublicInput(
+                "Window orders do not match the trace layout".to_string(),
+            ));
+        }
+        if !public_inputs
+            .positions
+            .iter()
+            .map(|position| position.window)
+            .eq(self.layout.positions.iter().copied())
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Public offsets do not match the trace layout".to_string(),
+            ));
+        }
+        if !public_inputs
+            .nth_occurrences
+            .iter()
+            .map(|nth| nth.window)
+            .eq(self.layout.nth_occurrences.iter().map(|nth| nth.window))
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Occurrence claims do not match the trace layout".to_string(),
+            ));
+        }
+        if public_inputs.prefixes != self.layout.prefixes
+            || public_inputs.suffixes != self.layout.suffixes
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Prefix or suffix windows do not match the trace layout".to_string(),
+            ));
+        }
+        if !public_inputs
+            .normalized
+            .iter()
+            .map(|normalized| (normalized.window, normalized.normalization.rules()))
+            .eq(self
+                .layout
+                .normalizers
+                .iter()
+                .map(|normalizer| (normalizer.window, normalizer.rules.clone())))
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Normalized windows do not match the trace layout".to_string(),
+            ));
+        }
+        if !public_inputs
+            .masked
+            .iter()
+            .map(|masked| masked.window)
+            .eq(self.layout.masked.iter().copied())
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Masked windows do not match the trace layout".to_string(),
+            ));
+        }
+        if !public_inputs
+            .redacted
+            .iter()
+            .map(|document| redaction::boundaries(&document.hidden).len())
+            .eq(self
+                .layout
+                .redactions
+                .iter()
+                .map(|redaction| redaction.boundary_cols.len()))
+        {
+            return Err(ZkpError::InvalidPublicInput(
+                "Redacted documents do not match the trace layout".to_string(),
+            ));
+        }
+        if public_inputs.counts.len() != self.layout.counts.len() {
+            return Err(ZkpError::InvalidPublicInput(format!(
+                "Counted string count mismatch: {} != {}",
+                public_inputs.counts.len(),
+                self.layout.counts.len()
+            )));
+        }
+
+        let matrix = to_row_major(trace);
+        let public_values = self.public_values::<F>(public_inputs);
+
+        self.check_rows(&matrix, &public_values)
+    }
+
+    /// 逐行检查约束，返回第一个不满足的约束
+    fn check_rows<F: Field>(
+        &self,
+        matrix: &RowMajorMatrix<F>,
+        public_values: &[F],
+    ) -> Result<(), ZkpError> {
+        let height = matrix.height();
+
+        for row in 0 .. height {
+            let local = matrix.row_slice(row).expect("row in range");
+            let next = matrix.row_slice((row + 1) % height).expect("row in range");
+
+            let mut checker = ConstraintChecker {
+                main: VerticalPair::new(
+                    RowMajorMatrixView::new_row(&local),
+                    RowMajorMatrixView::new_row(&next),
+                ),
+                public_values,
+                is_first_row: F::from_bool(row == 0),
+                is_last_row: F::from_bool(row == height - 1),
+                is_transition: F::from_bool(row != height - 1),
+                constraint_index: 0,
+                failed: Vec::new(),
+            };
+            self.eval(&mut checker);
+
+            if let Some(index) = checker.failed.first() {
+                return Err(ZkpError::ConstraintNotSatisfied(format!(
+                    "Constraint {} failed on row {}",
+                    index, row
+                )));
             }
         }
 
-        Ok(true)
+        Ok(())
+    }
+}
+
+impl<F> BaseAir<F> for SubstringAIR {
+    fn width(&self) -> usize {
+        self.layout.total_columns
     }
 }
 
-/// 约束多项式构建器
+impl<AB: AirBuilderWithPublicValues> Air<AB> for SubstringAIR {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (
+            main.row_slice(0).expect("The matrix is empty?"),
+            main.row_slice(1).expect("The matrix only has 1 row?"),
+        );
+        let pis: Vec<AB::Expr> = builder
+            .public_values()
+            .iter()
+            .map(|&value| value.into())
+            .collect();
+
+        // 1. SHA-256 约束
+        self.eval_sha256(builder, &local, &next, &pis);
+
+        // 2. 子串匹配约束
+        self.eval_substring(builder, &local, &pis);
+
+        // 3. 范围检查约束
+        self.eval_range(builder, &local);
+
+        // 4. 逻辑一致性约束
+        self.eval_logic(builder, &local, &next, &pis);
+
+        // 5. 子串不出现约束
+        self.eval_absence(builder, &local, &next, &pis);
+
+        // 6. 数值区间约束
+        self.eval_number(builder, &local, &next, &pis);
+
+        // 7. 正则匹配约束
+        self.eval_regex(builder, &local, &next);
+
+        // 8. 窗口先后约束
+        self.eval_order(builder, &local, &pis);
+
+        // 9. 窗口起点约束
+        self.eval_position(builder, &local, &pis);
+
+        // 10. 第 n 次出现约束
+        self.eval_nth_occurrence(builder, &local, &next, &pis);
+
+        // 11. 出现次数约束
+        self.eval_count(builder, &local, &next, &pis);
+
+        // 12. 首尾锚定约束
+        self.eval_text_ends(builder, &local, &next);
+
+        // 13. 规范化约束
+        self.eval_normalize(builder, &local, &next);
+
+        // 14. 涂黑文档约束
+        self.eval_redaction(builder, &local, &next, &pis);
+    }
+}
+
+/// 具体 trace 上的约束检查器
 ///
-/// 将约束转换为多项式形式，用于 STARK 证明系统
-#[derive(Debug, Clone)]
-pub struct ConstraintPolynomialBuilder<F: Field> {
-    air: SubstringAIR,
-    _phantom: std::marker::PhantomData<F>,
+/// 与 uni-stark 的调试构建器相同地提供 `(local, next)` 两行窗口与行选择子，
+/// 但记录失败的约束下标而不是直接 panic
+struct ConstraintChecker<'a, F: Field> {
+    main: VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>,
+    public_values: &'a [F],
+    is_first_row: F,
+    is_last_row: F,
+    is_transition: F,
+    constraint_index: usize,
+    failed: Vec<usize>,
 }
 
-impl<F: Field + QuotientMap<u8> + QuotientMap<usize>> ConstraintPolynomialBuilder<F> {
-    pub fn new(air: SubstringAIR) -> Self {
-        Self {
-            air,
-            _phantom: std::marker::PhantomData,
-        }
+impl<'a, F: Field> AirBuilder for ConstraintChecker<'a, F> {
+    type F = F;
+    type Expr = F;
+    type Var = F;
+    type M = VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>;
+
+    fn main(&self) -> Self::M {
+        self.main.clone()
     }
 
-    /// 构建所有约束多项式
-    pub fn build_constraint_polynomials(
-        &self,
-        trace: &[Vec<F>],
-        public_inputs: &PublicInputs,
-    ) -> Result<Vec<Vec<F>>, ZkpError> {
-        let constraints = self.air.evaluate_constraints(trace, public_inputs)?;
-
-        // 将约束转换为多项式表示
-        // 在 STARK 中，约束被表示为在某些点上求值为零的多项式
-        let trace_len = trace[0].len();
-        let mut polynomials = Vec::new();
-
-        // 每个约束对应一个多项式
-        for constraint in constraints {
-            let mut poly = vec![F::ZERO; trace_len];
-            // 简化版本：在所有点上设置约束值
-            for i in 0 .. trace_len {
-                poly[i] = constraint;
-            }
-            polynomials.push(poly);
+    fn is_first_row(&self) -> Self::Expr {
+        self.is_first_row
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.is_last_row
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        assert_eq!(size, 2, "only a window of two rows is supported");
+        self.is_transition
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        if x.into() != F::ZERO {
+            self.failed.push(self.constraint_index);
         }
+        self.constraint_index += 1;
+    }
+}
+
+impl<F: Field> AirBuilderWithPublicValues for ConstraintChecker<'_, F> {
+    type PublicVar = F;
 
-        Ok(polynomials)
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use p3_field::PrimeCharacteristicRing;
     use p3_goldilocks::Goldilocks;
     use sha2::{Digest, Sha256};
 
     use super::*;
     use crate::{
-        circuits::trace::{TraceGenerator, TraceLayout},
-        config::{CircuitWitness, PublicInputs},
+        circuits::{
+            bits::write_bits,
+            dfa::Dfa,
+            normalize::Normalization,
+            trace::{TraceGenerator, TraceLayout},
+        },
+        config::{
+            CircuitWitness, MaskedWindow, NormalizedWindow, NthOccurrence, OccurrenceCount,
+            PublicInputs, RedactedDocument, ValueRange, WindowInput, WindowOrder, WindowPosition,
+        },
     };
 
     #[test]
     fn test_constraint_evaluation() {
         let config = CircuitConfig::default();
-        let layout = TraceLayout::default();
+        let layout = TraceLayout::new(&config);
         let air = SubstringAIR::new(config.clone(), layout.clone());
 
         let plaintext = b"hello world!".to_vec();
@@ -295,70 +928,872 @@ mod tests {
         hasher.update(&plaintext);
         let commitment = hasher.finalize().into();
 
-        let public_inputs = PublicInputs {
-            commitment,
-            substring,
+        let public_inputs = PublicInputs::substring(commitment, substring);
+
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: public_inputs.clone(),
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![0],
+            }),
         };
 
+        let generator = TraceGenerator::new(config);
+        let mut trace = generator.generate_trace::<Goldilocks>(&params).unwrap();
+
+        // 诚实生成的 trace 满足全部约束
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+
+        // 篡改窗口内的原文字节后约束失败
+        trace[layout.plaintext_col][0] = Goldilocks::from_int(b'j');
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_err());
+    }
+
+    #[test]
+    fn test_constraints_bind_public_inputs() {
+        let config = CircuitConfig::default();
+        let layout = TraceLayout::new(&config);
+        let air = SubstringAIR::new(config.clone(), layout);
+
+        let plaintext = b"test".to_vec();
+        let public_inputs =
+            PublicInputs::substring(Sha256::digest(&plaintext).into(), b"te".to_vec());
+
         let params = crate::config::CircuitParams {
             config: config.clone(),
             public_inputs: public_inputs.clone(),
             witness: Some(CircuitWitness {
                 plaintext,
-                offset: 0,
+                offsets: vec![0],
             }),
         };
 
         let generator = TraceGenerator::new(config);
         let trace = generator.generate_trace::<Goldilocks>(&params).unwrap();
 
-        // 评估约束
-        let constraints = air.evaluate_constraints(&trace, &public_inputs).unwrap();
+        // 不同的承诺或子串长度都不能通过同一份 trace
+        let mut wrong_commitment = public_inputs.clone();
+        wrong_commitment.commitment[0] ^= 1;
+        assert!(
+            air.verify_all_constraints(&trace, &wrong_commitment)
+                .is_err()
+        );
 
-        println!("Generated {} constraints", constraints.len());
-        assert!(constraints.len() > 0);
+        let mut wrong_length = public_inputs;
+        wrong_length.windows[0].bytes = b"tes".to_vec();
+        assert!(air.verify_all_constraints(&trace, &wrong_length).is_err());
 
-        // 注意：由于我们使用了简化的 SHA-256 实现，
-        // 某些约束可能会失败，这在完整实现中会被修复
+        assert!(air.num_constraints() > 0);
     }
 
     #[test]
-    fn test_constraint_polynomial_building() {
+    fn test_sha256_constraints_reject_tampered_rounds() {
         let config = CircuitConfig::default();
-        let layout = TraceLayout::default();
-        let air = SubstringAIR::new(config.clone(), layout);
+        let layout = TraceLayout::new(&config);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
 
-        let builder = ConstraintPolynomialBuilder::<Goldilocks>::new(air);
+        let plaintext = b"hello world!".to_vec();
+        let public_inputs =
+            PublicInputs::substring(Sha256::digest(&plaintext).into(), b"world".to_vec());
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: public_inputs.clone(),
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![6],
+            }),
+        };
 
-        let plaintext = b"test".to_vec();
-        let substring = b"te".to_vec();
+        let trace = TraceGenerator::new(config)
+            .generate_trace::<Goldilocks>(&params)
+            .unwrap();
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+
+        // 篡改中间某一轮的工作变量
+        let mut tampered = trace.clone();
+        let col = layout.sha256.e_bits.start + 7;
+        tampered[col][20] = Goldilocks::ONE - tampered[col][20];
+        assert!(
+            air.verify_all_constraints(&tampered, &public_inputs)
+                .is_err()
+        );
+
+        // 篡改消息字
+        let mut tampered = trace.clone();
+        tampered[layout.sha256.w_cols.start][0] += Goldilocks::ONE;
+        assert!(
+            air.verify_all_constraints(&tampered, &public_inputs)
+                .is_err()
+        );
+
+        // 篡改最终链接值
+        let mut tampered = trace;
+        let last = tampered[0].len() - 1;
+        tampered[layout.sha256.hash_cols.start][last] += Goldilocks::ONE;
+        assert!(
+            air.verify_all_constraints(&tampered, &public_inputs)
+                .is_err()
+        );
+    }
 
-        let mut hasher = Sha256::new();
-        hasher.update(&plaintext);
-        let commitment = hasher.finalize().into();
+    #[test]
+    fn test_multi_block_sha256_padding() {
+        let config = CircuitConfig {
+            max_text_len: 200,
+            max_substring_len: 8,
+            enable_multi_block_sha: true,
+        };
+        let layout = TraceLayout::new(&config);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+        let generator = TraceGenerator::new(config.clone());
+
+        // 覆盖 0x80 与长度字段跨块的各种边界
+        for len in [0, 1, 55, 56, 63, 64, 119, 120, 200] {
+            let plaintext: Vec<u8> = (0 .. len).map(|i| (i * 7 + 3) as u8).collect();
+            let public_inputs = PublicInputs::substring(
+                Sha256::digest(&plaintext).into(),
+                plaintext[.. len.min(4)].to_vec(),
+            );
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: public_inputs.clone(),
+                witness: Some(CircuitWitness {
+                    plaintext,
+                    offsets: vec![0],
+                }),
+            };
+
+            let trace = generator.generate_trace::<Goldilocks>(&params).unwrap();
+            assert!(
+                air.verify_all_constraints(&trace, &public_inputs).is_ok(),
+                "length {len}"
+            );
+
+            // 篡改填充区的一个字节
+            let mut tampered = trace;
+            tampered[layout.sha256.byte_col][len + 1] += Goldilocks::ONE;
+            assert!(
+                air.verify_all_constraints(&tampered, &public_inputs)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_plaintext_column_bound_to_hashed_message() {
+        let config = CircuitConfig::default();
+        let layout = TraceLayout::new(&config);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let plaintext = b"hello world!".to_vec();
+        let public_inputs =
+            PublicInputs::substring(Sha256::digest(&plaintext).into(), b"hello".to_vec());
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: public_inputs.clone(),
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![0],
+            }),
+        };
+        let trace = TraceGenerator::new(config)
+            .generate_trace::<Goldilocks>(&params)
+            .unwrap();
+
+        // 原文列与子串列一起改写，子串匹配本身仍然成立，但与被哈希的消息不一致
+        let mut tampered = trace.clone();
+        tampered[layout.plaintext_col][0] = Goldilocks::from_int(b'j');
+        tampered[layout.windows[0].substring_col][0] = Goldilocks::from_int(b'j');
+        assert!(
+            air.verify_all_constraints(&tampered, &public_inputs)
+                .is_err()
+        );
+
+        // 原文列不能在填充区伪造字节
+        let mut tampered = trace;
+        tampered[layout.plaintext_col][20] = Goldilocks::from_int(b'x');
+        assert!(
+            air.verify_all_constraints(&tampered, &public_inputs)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_range_check_rejects_non_byte_values() {
+        let config = CircuitConfig::default();
+        let layout = TraceLayout::new(&config);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let plaintext = b"hello world!".to_vec();
+        let public_inputs =
+            PublicInputs::substring(Sha256::digest(&plaintext).into(), b"hello".to_vec());
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: public_inputs.clone(),
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![0],
+            }),
+        };
+        let mut trace = TraceGenerator::new(config)
+            .generate_trace::<Goldilocks>(&params)
+            .unwrap();
+
+        // 256·b₀ + b₁ == 256·(b₀ - 1) + (b₁ + 256)：消息字、原文列与子串列都保持一致，
+        // 只有字节范围约束能发现第1行的值超出 [0, 255]
+        let word_acc_col = layout.sha256.word_acc_col;
+        for col in [
+            layout.sha256.byte_col,
+            layout.plaintext_col,
+            layout.windows[0].substring_col,
+        ] {
+            trace[col][0] -= Goldilocks::ONE;
+            trace[col][1] += Goldilocks::from_int(256usize);
+        }
+        trace[word_acc_col][0] -= Goldilocks::ONE;
+
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_err());
+    }
+
+    #[test]
+    fn test_window_must_be_contiguous() {
+        let config = CircuitConfig::default();
+        let layout = TraceLayout::new(&config);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let plaintext = b"hello world!".to_vec();
+        let honest = PublicInputs::substring(Sha256::digest(&plaintext).into(), b"hel".to_vec());
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: honest.clone(),
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![0],
+            }),
+        };
+        let mut trace = TraceGenerator::new(config)
+            .generate_trace::<Goldilocks>(&params)
+            .unwrap();
+        assert!(air.verify_all_constraints(&trace, &honest).is_ok());
+
+        // 用第0、2、3行拼出原文中并不连续存在的 "hll"
+        let forged = PublicInputs::substring(honest.commitment, b"hll".to_vec());
+        let rows = [0, 2, 3];
+        for (row, in_window) in [true, false, true, true].into_iter().enumerate() {
+            trace[layout.windows[0].offset_indicator_col][row] = Goldilocks::from_bool(in_window);
+            trace[layout.windows[0].match_flag_col][row] = Goldilocks::from_bool(in_window);
+            trace[layout.windows[0].substring_col][row] = if in_window {
+                trace[layout.plaintext_col][row]
+            } else {
+                Goldilocks::ZERO
+            };
+            for col in layout.windows[0].substring_sel_cols.clone() {
+                trace[col][row] = Goldilocks::ZERO;
+            }
+        }
+        for (j, &row) in rows.iter().enumerate() {
+            trace[layout.windows[0].substring_sel_cols.start + j][row] = Goldilocks::ONE;
+        }
+        trace[layout.windows[0].window_count_col] = trace[layout.windows[0].offset_indicator_col]
+            .iter()
+            .scan(Goldilocks::ZERO, |count, &indicator| {
+                *count += indicator;
+                Some(*count)
+            })
+            .collect();
+
+        assert!(air.verify_all_constraints(&trace, &forged).is_err());
+    }
+
+    #[test]
+    fn test_anchored_windows_share_plaintext() {
+        let config = CircuitConfig::default();
+        let layout = TraceLayout::with_windows(&config, 2);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
 
+        let plaintext = b"report: hello world, platelet 50".to_vec();
         let public_inputs = PublicInputs {
-            commitment,
-            substring,
+            windows: vec![
+                WindowInput::new(b"hello world".to_vec()),
+                WindowInput::new(b"platelet 50".to_vec()),
+            ],
+            length: Some(plaintext.len()),
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
         };
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: public_inputs.clone(),
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![8, 21],
+            }),
+        };
+        let trace = TraceGenerator::with_layout(layout.clone(), config)
+            .generate_trace::<Goldilocks>(&params)
+            .unwrap();
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+
+        // 公开长度与哈希的长度字段绑定
+        let mut wrong_length = public_inputs.clone();
+        wrong_length.length = Some(31);
+        assert!(air.verify_all_constraints(&trace, &wrong_length).is_err());
+
+        // 目标窗口与锚文本窗口互不替代
+        let mut wrong_target = public_inputs.clone();
+        wrong_target.windows[1] = WindowInput::new(b"platelet 90".to_vec());
+        assert!(air.verify_all_constraints(&trace, &wrong_target).is_err());
+
+        let mut tampered = trace;
+        tampered[layout.windows[1].offset_indicator_col][21] = Goldilocks::ZERO;
+        assert!(
+            air.verify_all_constraints(&tampered, &public_inputs)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_range_number_parsing() {
+        let config = CircuitConfig::default();
+        let plaintext = b"platelet 250 k/uL".to_vec();
+        let inputs = |min, max| PublicInputs {
+            ranges: vec![ValueRange {
+                window: 0,
+                min,
+                max,
+            }],
+            ..PublicInputs::substring(Sha256::digest(&plaintext).into(), b"platelet ".to_vec())
+        };
+        let public_inputs = inputs(150, 450);
+        let layout = TraceLayout::for_inputs(&config, &public_inputs);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+        let number = layout.numbers[0].clone();
+
+        let trace_for = |public_inputs: &PublicInputs| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: public_inputs.clone(),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets: vec![0],
+                }),
+            };
+            TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap()
+        };
+        let trace = trace_for(&public_inputs);
+        let last = trace[0].len() - 1;
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+        assert_eq!(trace[number.value_col][last], Goldilocks::from_u64(250));
+
+        // 数值不在公开区间内
+        for other in [inputs(251, 450), inputs(150, 249)] {
+            assert!(air.verify_all_constraints(&trace, &other).is_err());
+        }
+
+        // 只截取数值的前缀（"25"）不满足约束，即使 25 落在区间内
+        let wide = inputs(0, 450);
+        let mut truncated = trace_for(&wide);
+        truncated[number.digit_flag_col][11] = Goldilocks::ZERO;
+        for row in 11 ..= last {
+            truncated[number.value_col][row] = Goldilocks::from_u64(25);
+            write_bits(&mut truncated, &number.value_bits, row, 25);
+        }
+        write_bits(&mut truncated, &number.lower_bits, last, 25);
+        write_bits(&mut truncated, &number.upper_bits, last, 425);
+        assert!(air.verify_all_constraints(&truncated, &wide).is_err());
+
+        // 数字段不能跳过标签之后的字节
+        let mut shifted = trace;
+        shifted[number.digit_flag_col][9] = Goldilocks::ZERO;
+        assert!(
+            air.verify_all_constraints(&shifted, &public_inputs)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_regex_walks_dfa() {
+        let config = CircuitConfig::default();
+        let plaintext = b"mail bob@example.com now".to_vec();
+        let public_inputs = PublicInputs {
+            dfas: vec![Dfa::compile(r"\w+@example\.com").unwrap()],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let layout = TraceLayout::for_inputs(&config, &public_inputs);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+        let regex = layout.regexes[0].clone();
 
         let params = crate::config::CircuitParams {
             config: config.clone(),
             public_inputs: public_inputs.clone(),
             witness: Some(CircuitWitness {
                 plaintext,
-                offset: 0,
+                offsets: Vec::new(),
             }),
         };
+        let trace = TraceGenerator::with_layout(layout, config)
+            .generate_trace::<Goldilocks>(&params)
+            .unwrap();
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+
+        // 跳过一步转移
+        let mut skipped = trace.clone();
+        let row = 5;
+        skipped[regex.state_col][row] = skipped[regex.state_col][row - 1];
+        assert!(
+            air.verify_all_constraints(&skipped, &public_inputs)
+                .is_err()
+        );
+
+        // 停在非接受状态
+        let mut rejected = trace;
+        let last = rejected[0].len() - 1;
+        for col in regex.accept_sel_cols.clone() {
+            rejected[col][last] = Goldilocks::ZERO;
+        }
+        assert!(
+            air.verify_all_constraints(&rejected, &public_inputs)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_absent_string_mismatch_witnesses() {
+        let config = CircuitConfig::default();
+        let plaintext = b"id=4417, status ok".to_vec();
+        let inputs = |absent: &[u8]| PublicInputs {
+            absent: vec![WindowInput::new(absent.to_vec())],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let public_inputs = inputs(b"id=4418");
+        let layout = TraceLayout::for_inputs(&config, &public_inputs);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+        let absence = layout.absent[0].clone();
+
+        let trace_for = |public_inputs: &PublicInputs| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: public_inputs.clone(),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets: Vec::new(),
+                }),
+            };
+            TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+        };
+        let trace = trace_for(&public_inputs).unwrap();
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+
+        // 出现的字符串没有失配见证
+        assert!(matches!(
+            trace_for(&inputs(b"id=4417")),
+            Err(ZkpError::InvalidWitness(_))
+        ));
+        assert!(
+            air.verify_all_constraints(&trace, &inputs(b"id=4417"))
+                .is_err()
+        );
+
+        // 失配下标必须落在字符串长度之内
+        let mut out_of_range = trace;
+        let mismatch = |j: usize| absence.mismatch.sel_cols.start + j;
+        out_of_range[mismatch(6)][0] = Goldilocks::ZERO;
+        out_of_range[mismatch(8)][0] = Goldilocks::ONE;
+        let diff = out_of_range[absence.mismatch.shift.cols.start + 8][0];
+        out_of_range[absence.mismatch.inverse_col][0] = diff.inverse();
+        assert!(
+            air.verify_all_constraints(&out_of_range, &public_inputs)
+                .is_err()
+        );
+    }
 
-        let generator = TraceGenerator::new(config);
-        let trace = generator.generate_trace::<Goldilocks>(&params).unwrap();
+    #[test]
+    fn test_ordered_windows() {
+        let config = CircuitConfig::default();
+        let plaintext = b"clause X; clause Y; clause X".to_vec();
+        let inputs = |after: &[u8]| PublicInputs {
+            windows: vec![
+                WindowInput::new(b"clause".to_vec()),
+                WindowInput::new(after.to_vec()),
+            ],
+            orders: vec![WindowOrder {
+                before: 0,
+                after: 1,
+            }],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let layout = TraceLayout::for_inputs(&config, &inputs(b" X"));
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let check = |after: &[u8], offsets: Vec<usize>| {
+            let public_inputs = inputs(after);
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: public_inputs.clone(),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets,
+                }),
+            };
+            let trace = TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap();
+            air.verify_all_constraints(&trace, &public_inputs).is_ok()
+        };
+
+        // 可以紧接在前一个窗口之后开始
+        assert!(check(b" X", vec![0, 6]));
+        assert!(check(b" X", vec![0, 26]));
+        assert!(check(b"se X", vec![0, 24]));
+        // 先后颠倒或相互重叠都不满足约束
+        assert!(!check(b" X", vec![20, 6]));
+        assert!(!check(b"se X", vec![0, 4]));
+        assert!(!check(b"se X", vec![20, 24]));
+
+        // 先后顺序属于电路结构，不能被去掉
+        let mut unordered = inputs(b" X");
+        unordered.orders.clear();
+        let trace = vec![vec![Goldilocks::ZERO; 64]; layout.total_columns];
+        assert!(air.verify_all_constraints(&trace, &unordered).is_err());
+    }
+
+    #[test]
+    fn test_public_window_offset() {
+        let config = CircuitConfig::default();
+        let plaintext = b"hello, hello world".to_vec();
+        let inputs = |offset| PublicInputs {
+            windows: vec![WindowInput::new(b"hello".to_vec())],
+            positions: vec![WindowPosition { window: 0, offset }],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let layout = TraceLayout::for_inputs(&config, &inputs(7));
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let trace_at = |offset| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: inputs(offset),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets: vec![offset],
+                }),
+            };
+            TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap()
+        };
+
+        for offset in [0, 7] {
+            assert!(
+                air.verify_all_constraints(&trace_at(offset), &inputs(offset))
+                    .is_ok()
+            );
+        }
+        // 窗口起点必须等于公开偏移，即使子串在别处也出现
+        assert!(
+            air.verify_all_constraints(&trace_at(0), &inputs(7))
+                .is_err()
+        );
+        assert!(
+            air.verify_all_constraints(&trace_at(7), &inputs(0))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_nth_occurrence_counts_earlier_matches() {
+        let config = CircuitConfig::default();
+        let plaintext = b"aab; ab; xab".to_vec();
+        let inputs = |n| PublicInputs {
+            windows: vec![WindowInput::new(b"ab".to_vec())],
+            nth_occurrences: vec![NthOccurrence { window: 0, n }],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let layout = TraceLayout::for_inputs(&config, &inputs(1));
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let trace_at = |offset| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: inputs(1),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets: vec![offset],
+                }),
+            };
+            TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap()
+        };
+
+        // "ab" 出现在 1、5、10
+        for (n, offset) in [(1, 1), (2, 5), (3, 10)] {
+            let trace = trace_at(offset);
+            assert!(air.verify_all_constraints(&trace, &inputs(n)).is_ok());
+            assert!(air.verify_all_constraints(&trace, &inputs(n + 1)).is_err());
+        }
+
+        // 不能把一次出现标成未出现来减少计数
+        let mut tampered = trace_at(5);
+        let occurrence = &layout.nth_occurrences[0].occurrence;
+        tampered[occurrence.match_col][1] = Goldilocks::ZERO;
+        tampered[occurrence.mismatch.sel_cols.start][1] = Goldilocks::ONE;
+        for row in 1 .. tampered[0].len() {
+            tampered[occurrence.count_col][row] -= Goldilocks::ONE;
+        }
+        assert!(air.verify_all_constraints(&tampered, &inputs(1)).is_err());
+    }
+
+    #[test]
+    fn test_occurrence_count_bounds() {
+        let config = CircuitConfig::default();
+        let plaintext = b"ERROR a; ERROR b; ERRORERROR".to_vec();
+        let inputs = |value: &[u8], min, max| PublicInputs {
+            counts: vec![OccurrenceCount {
+                bytes: value.to_vec(),
+                min,
+                max,
+            }],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let layout = TraceLayout::for_inputs(&config, &inputs(b"ERROR", 0, 0));
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let check = |value: &[u8], min, max| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: inputs(value, min, max),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets: vec![],
+                }),
+            };
+            let trace = TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap();
+            air.verify_all_constraints(&trace, &params.public_inputs)
+                .is_ok()
+        };
+
+        assert!(check(b"ERROR", 4, 4));
+        assert!(check(b"ERROR", 3, 10));
+        assert!(check(b"RERR", 1, 1));
+        assert!(check(b"WARN", 0, 0));
+
+        // 同一份 trace 不满足其他计数
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: inputs(b"ERROR", 4, 4),
+            witness: Some(CircuitWitness {
+                plaintext: plaintext.clone(),
+                offsets: vec![],
+            }),
+        };
+        let trace = TraceGenerator::with_layout(layout.clone(), config.clone())
+            .generate_trace::<Goldilocks>(&params)
+            .unwrap();
+        for (value, min, max) in [
+            (&b"ERROR"[..], 3, 3),
+            (b"ERROR", 5, 5),
+            (b"ERROR", 0, 3),
+            (b"ERRORS", 4, 4),
+        ] {
+            assert!(
+                air.verify_all_constraints(&trace, &inputs(value, min, max))
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_prefix_and_suffix_windows() {
+        let config = CircuitConfig::default();
+        let plaintext = b"%PDF-1.7 ... %PDF; signed ... signed".to_vec();
+        let public_inputs = PublicInputs {
+            windows: vec![
+                WindowInput::new(b"%PDF".to_vec()),
+                WindowInput::new(b"signed".to_vec()),
+            ],
+            length: Some(plaintext.len()),
+            prefixes: vec![0],
+            suffixes: vec![1],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let layout = TraceLayout::for_inputs(&config, &public_inputs);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let check = |offsets: Vec<usize>| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: public_inputs.clone(),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets,
+                }),
+            };
+            let trace = TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap();
+            air.verify_all_constraints(&trace, &public_inputs).is_ok()
+        };
+
+        assert!(check(vec![0, 30]));
+        // 其他位置上的出现不是前缀或后缀
+        assert!(!check(vec![13, 30]));
+        assert!(!check(vec![0, 19]));
+
+        let mut unanchored = public_inputs.clone();
+        unanchored.suffixes.clear();
+        let trace = vec![vec![Goldilocks::ZERO; 64]; layout.total_columns];
+        assert!(air.verify_all_constraints(&trace, &unanchored).is_err());
+    }
 
-        let polynomials = builder
-            .build_constraint_polynomials(&trace, &public_inputs)
+    #[test]
+    fn test_normalized_windows() {
+        let config = CircuitConfig::default();
+        let plaintext = "年龄　４２ Status: ERROR".as_bytes().to_vec();
+        let normalization = Normalization {
+            case_fold: true,
+            full_width: true,
+        };
+        let inputs = |values: [&str; 2]| PublicInputs {
+            windows: values
+                .iter()
+                .map(|value| WindowInput::new(normalization.apply(value.as_bytes())))
+                .collect(),
+            normalized: (0 .. 2)
+                .map(|window| NormalizedWindow {
+                    window,
+                    normalization,
+                })
+                .collect(),
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let public_inputs = inputs(["年龄 42", "status: error"]);
+        let layout = TraceLayout::for_inputs(&config, &public_inputs);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let trace_for = |public_inputs: &PublicInputs, offsets: Vec<usize>| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: public_inputs.clone(),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets,
+                }),
+            };
+            TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap()
+        };
+
+        // 全角空格与全角数字映射为半角，大写字母折叠为小写
+        let trace = trace_for(&public_inputs, vec![0, 16]);
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+
+        let wrong = inputs(["年龄 43", "status: error"]);
+        let trace = trace_for(&wrong, vec![0, 16]);
+        assert!(air.verify_all_constraints(&trace, &wrong).is_err());
+
+        // 规范化列必须等于选中规则的输出
+        let mut tampered = trace_for(&public_inputs, vec![0, 16]);
+        let normalizer = &layout.normalizers[0];
+        tampered[normalizer.normalized_col][9] = Goldilocks::from_u8(b'5');
+        assert!(
+            air.verify_all_constraints(&tampered, &public_inputs)
+                .is_err()
+        );
+
+        let mut raw = public_inputs.clone();
+        raw.normalized.clear();
+        assert!(air.verify_all_constraints(&trace, &raw).is_err());
+    }
+
+    #[test]
+    fn test_masked_window_skips_hidden_bytes() {
+        let config = CircuitConfig::default();
+        let plaintext = b"user ID: 8731-1234 ok".to_vec();
+        let hidden: Vec<bool> = (0 .. 13).map(|j| (4 .. 8).contains(&j)).collect();
+        let inputs = |value: &[u8], hidden: &[bool]| PublicInputs {
+            windows: vec![WindowInput::new(value.to_vec())],
+            masked: vec![MaskedWindow {
+                window: 0,
+                hidden: hidden.to_vec(),
+            }],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let public_inputs = inputs(b"ID: \0\0\0\0-1234", &hidden);
+        let layout = TraceLayout::for_inputs(&config, &public_inputs);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let check = |public_inputs: &PublicInputs, verified: &PublicInputs| {
+            let params = crate::config::CircuitParams {
+                config: config.clone(),
+                public_inputs: public_inputs.clone(),
+                witness: Some(CircuitWitness {
+                    plaintext: plaintext.clone(),
+                    offsets: vec![5],
+                }),
+            };
+            let trace = TraceGenerator::with_layout(layout.clone(), config.clone())
+                .generate_trace::<Goldilocks>(&params)
+                .unwrap();
+            air.verify_all_constraints(&trace, verified).is_ok()
+        };
+
+        assert!(check(&public_inputs, &public_inputs));
+
+        // 未隐藏的字节仍逐字节比较
+        let wrong = inputs(b"ID: \0\0\0\0-1235", &hidden);
+        assert!(!check(&wrong, &wrong));
+
+        // 隐藏标志来自公开值，不能由证明者扩大
+        let unmasked = inputs(b"ID: \0\0\0\0-1234", &[false; 13]);
+        assert!(!check(&public_inputs, &unmasked));
+    }
+
+    #[test]
+    fn test_redacted_document_mask_and_fingerprint() {
+        let config = CircuitConfig::default();
+        let plaintext = b"Patient: Alice Smith, stable".to_vec();
+        let inputs = |document: &[u8], hidden: std::ops::Range<usize>| PublicInputs {
+            length: Some(document.len()),
+            redacted: vec![RedactedDocument {
+                bytes: document
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &byte)| if hidden.contains(&k) { 0 } else { byte })
+                    .collect(),
+                hidden: (0 .. document.len()).map(|k| hidden.contains(&k)).collect(),
+            }],
+            ..PublicInputs::new(Sha256::digest(&plaintext).into())
+        };
+        let public_inputs = inputs(b"Patient: ##### #####, stable", 9 .. 20);
+        let layout = TraceLayout::for_inputs(&config, &public_inputs);
+        let air = SubstringAIR::new(config.clone(), layout.clone());
+
+        let params = crate::config::CircuitParams {
+            config: config.clone(),
+            public_inputs: public_inputs.clone(),
+            witness: Some(CircuitWitness {
+                plaintext: plaintext.clone(),
+                offsets: vec![],
+            }),
+        };
+        let trace = TraceGenerator::with_layout(layout, config.clone())
+            .generate_trace::<Goldilocks>(&params)
             .unwrap();
+        assert!(air.verify_all_constraints(&trace, &public_inputs).is_ok());
+
+        // 未涂黑的字节进入指纹，改动任意一个都不再相等
+        let wrong = inputs(b"Patient: ##### #####, stab1e", 9 .. 20);
+        assert!(air.verify_all_constraints(&trace, &wrong).is_err());
 
-        println!("Generated {} constraint polynomials", polynomials.len());
-        assert!(polynomials.len() > 0);
+        // 掩码由公开边界确定，不能由证明者移动
+        let shifted = inputs(b"Patient: ##### #####, stable", 10 .. 21);
+        assert!(air.verify_all_constraints(&trace, &shifted).is_err());
     }
 }
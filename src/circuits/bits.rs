@@ -0,0 +1,18 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+/// 按小端写入数值的位分解
+pub fn write_bits<F: Field>(trace: &mut [Vec<F>], cols: &Range<usize>, row: usize, value: u64) {
+    for (i, col) in cols.clone().enumerate() {
+        trace[col][row] = F::from_bool((value >> i) & 1 == 1);
+    }
+}
+
+/// 把小端位列打包成数值 Σ bits[i]·2ⁱ
+pub fn pack<AB: AirBuilder>(bits: &[AB::Var]) -> AB::Expr {
+    bits.iter()
+        .rev()
+        .fold(AB::Expr::ZERO, |acc, &bit| acc.double() + bit)
+}
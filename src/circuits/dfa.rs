@@ -0,0 +1,643 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ZkpError;
+
+/// DFA 状态数上限
+pub const MAX_DFA_STATES: usize = 128;
+/// DFA 边数上限（每条边在 trace 中占一列选择子，边过多会使电路过宽）
+pub const MAX_DFA_EDGES: usize = 512;
+/// NFA 状态数上限
+const MAX_NFA_STATES: usize = 4096;
+/// `{n,m}` 重复次数上限
+const MAX_REPEAT: u32 = 64;
+
+/// DFA 的一条边：在 `from` 状态读入 `[lo, hi]` 内的字节后到达 `to`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DfaEdge {
+    pub from: u32,
+    pub lo: u8,
+    pub hi: u8,
+    pub to: u32,
+}
+
+impl DfaEdge {
+    fn contains(&self, byte: u8) -> bool {
+        (self.lo ..= self.hi).contains(&byte)
+    }
+}
+
+/// 由正则表达式编译出的字节级确定有限自动机
+///
+/// 状态0为初始状态。死状态（再也无法接受）及其入边被省略，读入没有出边的字节即匹配失败。
+/// 匹配语义与常见的正则搜索一致：没有 `^` 时匹配可以从任意位置开始，
+/// 没有 `$` 时匹配之后的字节不再影响结果
+///
+/// 支持的语法：字面字符（非 ASCII 字符按 UTF-8 字节序列匹配）、`.`、`[...]`/`[^...]`、
+/// `\d \w \s \D \W \S`、转义标点、`* + ? {n} {n,} {n,m}`、`|`、`(...)`/`(?:...)`，
+/// 以及只出现在开头的 `^` 与只出现在结尾的 `$`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dfa {
+    /// 状态个数
+    pub num_states: usize,
+    /// 所有边，按起始状态与字节区间排序
+    pub edges: Vec<DfaEdge>,
+    /// 接受状态
+    pub accepting: Vec<u32>,
+}
+
+impl Dfa {
+    /// 编译正则表达式
+    pub fn compile(pattern: &str) -> Result<Self, ZkpError> {
+        let (anchored_start, body) = match pattern.strip_prefix('^') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let (anchored_end, body) = match body.strip_suffix('$') {
+            // 结尾的 `\$` 是字面的美元符
+            Some(rest) if rest.chars().rev().take_while(|&c| c == '\\').count() % 2 == 0 => {
+                (true, rest)
+            }
+            _ => (false, body),
+        };
+
+        let ast = Parser::new(body).parse()?;
+        let nfa = Nfa::build(&ast)?;
+        Self::determinize(&nfa, anchored_start, anchored_end)
+    }
+
+    /// 在 `state` 读入 `byte` 时走的边的下标
+    pub fn step(&self, state: u32, byte: u8) -> Option<usize> {
+        self.edges
+            .iter()
+            .position(|edge| edge.from == state && edge.contains(byte))
+    }
+
+    /// 在输入上运行自动机，返回每个字节走过的边；中途没有出边时返回 `None`
+    pub fn run(&self, input: &[u8]) -> Option<Vec<usize>> {
+        let mut state = 0;
+        input
+            .iter()
+            .map(|&byte| {
+                let edge = self.step(state, byte)?;
+                state = self.edges[edge].to;
+                Some(edge)
+            })
+            .collect()
+    }
+
+    /// 输入是否匹配
+    pub fn is_match(&self, input: &[u8]) -> bool {
+        let Some(path) = self.run(input) else {
+            return false;
+        };
+        let state = path.last().map_or(0, |&edge| self.edges[edge].to);
+        self.accepting.contains(&state)
+    }
+
+    /// 子集构造
+    fn determinize(nfa: &Nfa, anchored_start: bool, anchored_end: bool) -> Result<Self, ZkpError> {
+        // 未锚定结尾时，一旦到达接受状态就进入吸收的“已匹配”状态
+        let matched: BTreeSet<usize> = BTreeSet::from([usize::MAX]);
+        let start = nfa.closure([nfa.start]);
+        let normalize = |set: BTreeSet<usize>| {
+            if !anchored_end && set.contains(&nfa.accept) {
+                matched.clone()
+            } else {
+                set
+            }
+        };
+
+        let classes = nfa.byte_classes();
+        let mut ids = BTreeMap::from([(normalize(start.clone()), 0u32)]);
+        let mut queue = VecDeque::from([normalize(start.clone())]);
+        let mut edges = Vec::new();
+        let mut accepting = Vec::new();
+
+        while let Some(set) = queue.pop_front() {
+            let from = ids[&set];
+            if set == matched || set.contains(&nfa.accept) {
+                accepting.push(from);
+            }
+
+            for &(lo, hi) in &classes {
+                let target = if set == matched {
+                    matched.clone()
+                } else {
+                    let mut moved = nfa.closure(nfa.step(&set, lo));
+                    if !anchored_start {
+                        moved.extend(&start);
+                    }
+                    normalize(moved)
+                };
+                // 死状态不生成边
+                if target.is_empty() {
+                    continue;
+                }
+
+                let next_id = ids.len() as u32;
+                let to = *ids.entry(target.clone()).or_insert_with(|| {
+                    queue.push_back(target);
+                    next_id
+                });
+                if ids.len() > MAX_DFA_STATES {
+                    return Err(ZkpError::InvalidPublicInput(format!(
+                        "Pattern needs more than {} DFA states",
+                        MAX_DFA_STATES
+                    )));
+                }
+
+                // 合并相邻且目标相同的字节区间
+                match edges.last_mut() {
+                    Some(DfaEdge {
+                        from: last_from,
+                        hi: last_hi,
+                        to: last_to,
+                        ..
+                    }) if *last_from == from
+                        && *last_to == to
+                        && *last_hi as u16 + 1 == lo as u16 =>
+                    {
+                        *last_hi = hi;
+                    }
+                    _ => edges.push(DfaEdge { from, lo, hi, to }),
+                }
+            }
+        }
+
+        if edges.len() > MAX_DFA_EDGES {
+            return Err(ZkpError::InvalidPublicInput(format!(
+                "Pattern needs more than {} DFA edges",
+                MAX_DFA_EDGES
+            )));
+        }
+
+        accepting.sort_unstable();
+        Ok(Self {
+            num_states: ids.len(),
+            edges,
+            accepting,
+        })
+    }
+}
+
+/// 正则语法树
+#[derive(Clone, Debug)]
+enum Node {
+    /// 字节集合（已排序、合并的闭区间）
+    Class(Vec<(u8, u8)>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+impl Node {
+    fn byte(byte: u8) -> Self {
+        Node::Class(vec![(byte, byte)])
+    }
+
+    /// 字面字符，非 ASCII 字符展开为 UTF-8 字节序列
+    fn literal(c: char) -> Self {
+        let mut buf = [0; 4];
+        let bytes = c.encode_utf8(&mut buf).as_bytes();
+        match bytes {
+            [byte] => Node::byte(*byte),
+            _ => Node::Concat(bytes.iter().map(|&byte| Node::byte(byte)).collect()),
+        }
+    }
+}
+
+/// 递归下降的正则解析器
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(pattern: &str) -> Self {
+        Self {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> ZkpError {
+        ZkpError::InvalidPublicInput(format!("Invalid pattern at {}: {}", self.pos, message))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse(mut self) -> Result<Node, ZkpError> {
+        let node = self.parse_alt()?;
+        match self.peek() {
+            None => Ok(node),
+            Some(_) => Err(self.error("unmatched ')'")),
+        }
+    }
+
+    fn parse_alt(&mut self) -> Result<Node, ZkpError> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.eat('|') {
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alt(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, ZkpError> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            items.push(self.parse_repeat()?);
+        }
+        Ok(Node::Concat(items))
+    }
+
+    fn parse_repeat(&mut self) -> Result<Node, ZkpError> {
+        let mut node = self.parse_atom()?;
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    self.pos += 1;
+                    self.parse_bounds()?
+                }
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+            };
+        }
+    }
+
+    /// 解析 `{n}`、`{n,}`、`{n,m}` 中 `{` 之后的部分，停在 `}` 上
+    fn parse_bounds(&mut self) -> Result<(u32, Option<u32>), ZkpError> {
+        let min = self.parse_number()?;
+        let max = if self.eat(',') {
+            if self.peek() == Some('}') {
+                None
+            } else {
+                Some(self.parse_number()?)
+            }
+        } else {
+            Some(min)
+        };
+        if self.peek() != Some('}') {
+            return Err(self.error("expected '}'"));
+        }
+        if max.is_some_and(|max| max < min) || min.max(max.unwrap_or(0)) > MAX_REPEAT {
+            return Err(self.error("invalid repetition bounds"));
+        }
+        Ok((min, max))
+    }
+
+    fn parse_number(&mut self) -> Result<u32, ZkpError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start .. self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .map_err(|_| self.error("expected a number"))
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, ZkpError> {
+        let c = self.next().ok_or_else(|| self.error("unexpected end"))?;
+        match c {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err(self.error("only non-capturing groups (?:...) are supported"));
+                }
+                let node = self.parse_alt()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(node)
+            }
+            '[' => self.parse_class(),
+            '.' => Ok(Node::Class(vec![(0, b'\n' - 1), (b'\n' + 1, u8::MAX)])),
+            '\\' => self.parse_escape().map(|escape| match escape {
+                Escape::Class(ranges) => Node::Class(ranges),
+                Escape::Literal(c) => Node::literal(c),
+            }),
+            '^' | '$' => Err(self.error("anchors are only supported at the start and end")),
+            '*' | '+' | '?' | '{' => Err(self.error("repetition without an operand")),
+            c => Ok(Node::literal(c)),
+        }
+    }
+
+    /// 解析 `\` 之后的转义
+    fn parse_escape(&mut self) -> Result<Escape, ZkpError> {
+        let c = self.next().ok_or_else(|| self.error("dangling escape"))?;
+        let digit = vec![(b'0', b'9')];
+        let word = vec![(b'0', b'9'), (b'A', b'Z'), (b'_', b'_'), (b'a', b'z')];
+        let space = vec![(b'\t', b'\r'), (b' ', b' ')];
+        Ok(match c {
+            'd' => Escape::Class(digit),
+            'w' => Escape::Class(word),
+            's' => Escape::Class(space),
+            'D' => Escape::Class(complement(&digit)),
+            'W' => Escape::Class(complement(&word)),
+            'S' => Escape::Class(complement(&space)),
+            'n' => Escape::Literal('\n'),
+            't' => Escape::Literal('\t'),
+            'r' => Escape::Literal('\r'),
+            c if c.is_ascii_punctuation() => Escape::Literal(c),
+            _ => return Err(self.error("unsupported escape")),
+        })
+    }
+
+    /// 解析 `[` 之后的字节集合（只支持 ASCII）
+    fn parse_class(&mut self) -> Result<Node, ZkpError> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .next()
+                .ok_or_else(|| self.error("unterminated class"))?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let lo = match c {
+                '\\' => match self.parse_escape()? {
+                    Escape::Class(class) => {
+                        ranges.extend(class);
+                        continue;
+                    }
+                    Escape::Literal(c) => c,
+                },
+                c => c,
+            };
+            let hi = if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+                match self.next() {
+                    Some('\\') => match self.parse_escape()? {
+                        Escape::Literal(c) => c,
+                        Escape::Class(_) => return Err(self.error("invalid class range")),
+                    },
+                    Some(c) => c,
+                    None => return Err(self.error("unterminated class")),
+                }
+            } else {
+                lo
+            };
+            if !lo.is_ascii() || !hi.is_ascii() || lo > hi {
+                return Err(self.error("class ranges must be ASCII and ordered"));
+            }
+            ranges.push((lo as u8, hi as u8));
+        }
+
+        let ranges = normalize(ranges);
+        Ok(Node::Class(if negated {
+            complement(&ranges)
+        } else {
+            ranges
+        }))
+    }
+}
+
+/// 转义的结果：字节集合或单个字符
+enum Escape {
+    Class(Vec<(u8, u8)>),
+    Literal(char),
+}
+
+/// 排序并合并重叠或相邻的区间
+fn normalize(mut ranges: Vec<(u8, u8)>) -> Vec<(u8, u8)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u8, u8)> = Vec::with_capacity(ranges.len());
+    for (lo, hi) in ranges {
+        match merged.last_mut() {
+            Some((_, last_hi)) if lo as u16 <= *last_hi as u16 + 1 => {
+                *last_hi = (*last_hi).max(hi);
+            }
+            _ => merged.push((lo, hi)),
+        }
+    }
+    merged
+}
+
+/// 区间集合关于 `[0, 255]` 的补集
+fn complement(ranges: &[(u8, u8)]) -> Vec<(u8, u8)> {
+    let mut result = Vec::new();
+    let mut next = 0u16;
+    for &(lo, hi) in &normalize(ranges.to_vec()) {
+        if (lo as u16) > next {
+            result.push((next as u8, lo - 1));
+        }
+        next = hi as u16 + 1;
+    }
+    if next <= u8::MAX as u16 {
+        result.push((next as u8, u8::MAX));
+    }
+    result
+}
+
+/// Thompson NFA 的状态
+#[derive(Clone, Debug)]
+enum NfaState {
+    /// 读入区间内的字节后到达下一个状态
+    Bytes(Vec<(u8, u8)>, usize),
+    /// ε 转移
+    Split(Vec<usize>),
+    Accept,
+}
+
+/// Thompson 构造的 NFA
+struct Nfa {
+    states: Vec<NfaState>,
+    start: usize,
+    accept: usize,
+}
+
+impl Nfa {
+    fn build(node: &Node) -> Result<Self, ZkpError> {
+        let mut nfa = Self {
+            states: vec![NfaState::Accept],
+            start: 0,
+            accept: 0,
+        };
+        nfa.start = nfa.compile(node, nfa.accept)?;
+        Ok(nfa)
+    }
+
+    fn push(&mut self, state: NfaState) -> Result<usize, ZkpError> {
+        if self.states.len() >= MAX_NFA_STATES {
+            return Err(ZkpError::InvalidPublicInput(format!(
+                "Pattern needs more than {} NFA states",
+                MAX_NFA_STATES
+            )));
+        }
+        self.states.push(state);
+        Ok(self.states.len() - 1)
+    }
+
+    /// 把 `node` 编译为以 `next` 为后继的片段，返回片段入口
+    fn compile(&mut self, node: &Node, next: usize) -> Result<usize, ZkpError> {
+        match node {
+            Node::Class(ranges) => self.push(NfaState::Bytes(ranges.clone(), next)),
+            Node::Concat(items) => items
+                .iter()
+                .rev()
+                .try_fold(next, |next, item| self.compile(item, next)),
+            Node::Alt(branches) => {
+                let starts = branches
+                    .iter()
+                    .map(|branch| self.compile(branch, next))
+                    .collect::<Result<_, _>>()?;
+                self.push(NfaState::Split(starts))
+            }
+            Node::Repeat { node, min, max } => {
+                // 先编译可选部分：无上界时为循环，否则为 max - min 个嵌套的可选副本
+                let mut tail = match max {
+                    None => {
+                        let lp = self.push(NfaState::Split(Vec::new()))?;
+                        let body = self.compile(node, lp)?;
+                        self.states[lp] = NfaState::Split(vec![body, next]);
+                        lp
+                    }
+                    Some(max) => {
+                        let mut tail = next;
+                        for _ in *min .. *max {
+                            let body = self.compile(node, tail)?;
+                            tail = self.push(NfaState::Split(vec![body, next]))?;
+                        }
+                        tail
+                    }
+                };
+                for _ in 0 .. *min {
+                    tail = self.compile(node, tail)?;
+                }
+                Ok(tail)
+            }
+        }
+    }
+
+    /// ε 闭包
+    fn closure(&self, states: impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+        let mut set = BTreeSet::new();
+        let mut stack: Vec<usize> = states.into_iter().collect();
+        while let Some(state) = stack.pop() {
+            if set.insert(state)
+                && let NfaState::Split(targets) = &self.states[state]
+            {
+                stack.extend(targets);
+            }
+        }
+        set
+    }
+
+    /// 读入一个字节后到达的状态（不含 ε 闭包）
+    fn step(&self, set: &BTreeSet<usize>, byte: u8) -> Vec<usize> {
+        set.iter()
+            .filter_map(|&state| match &self.states[state] {
+                NfaState::Bytes(ranges, next)
+                    if ranges.iter().any(|&(lo, hi)| (lo ..= hi).contains(&byte)) =>
+                {
+                    Some(*next)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 把 `[0, 255]` 划分为若干区间，同一区间内的字节在所有状态上转移相同
+    fn byte_classes(&self) -> Vec<(u8, u8)> {
+        let mut boundaries = BTreeSet::from([0u16, 256]);
+        for state in &self.states {
+            if let NfaState::Bytes(ranges, _) = state {
+                for &(lo, hi) in ranges {
+                    boundaries.insert(lo as u16);
+                    boundaries.insert(hi as u16 + 1);
+                }
+            }
+        }
+        let boundaries: Vec<u16> = boundaries.into_iter().collect();
+        boundaries
+            .windows(2)
+            .map(|pair| (pair[0] as u8, (pair[1] - 1) as u8))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dfa_matching() {
+        let email = Dfa::compile(r"[a-z0-9._%+-]+@example\.com").unwrap();
+        assert!(email.is_match(b"contact: alice.b@example.com today"));
+        assert!(!email.is_match(b"contact: alice@example.org"));
+        assert!(!email.is_match(b"@example.com"));
+
+        let anchored = Dfa::compile(r"^id:\d{3}(-[A-Z]{2})?$").unwrap();
+        assert!(anchored.is_match(b"id:123"));
+        assert!(anchored.is_match(b"id:123-AB"));
+        assert!(!anchored.is_match(b" id:123"));
+        assert!(!anchored.is_match(b"id:1234"));
+        assert!(!anchored.is_match(b"id:123-A"));
+
+        let alternation = Dfa::compile("(?:cat|dog)s?$").unwrap();
+        assert!(alternation.is_match(b"hot dogs"));
+        assert!(!alternation.is_match(b"cats!"));
+
+        let unicode = Dfa::compile("你好").unwrap();
+        assert!(unicode.is_match("说你好".as_bytes()));
+    }
+
+    #[test]
+    fn test_dfa_rejects_unsupported_patterns() {
+        for pattern in [
+            "(abc", "abc)", "a{3,1}", "*a", "a^b", r"\q", "[z-a]", "(?=a)",
+        ] {
+            assert!(
+                matches!(Dfa::compile(pattern), Err(ZkpError::InvalidPublicInput(_))),
+                "{pattern}"
+            );
+        }
+
+        // 61个状态未超出上限，但每个状态有11条边
+        assert!(matches!(
+            Dfa::compile("[02468]{60}"),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+    }
+}
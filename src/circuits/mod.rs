@@ -1,6 +1,16 @@
 pub mod substring_circuit;
 pub mod trace;
 pub mod air;
+pub mod sha256;
+pub mod bits;
+pub mod number;
+pub mod dfa;
+pub mod regex;
+pub mod absence;
+pub mod shift;
+pub mod occurrence;
+pub mod normalize;
+pub mod redaction;
 
 use crate::config::{CircuitConfig, CircuitParams};
 use crate::error::ZkpError;
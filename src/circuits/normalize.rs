@@ -0,0 +1,353 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+use serde::{Deserialize, Serialize};
+
+use crate::circuits::{
+    bits::{pack, write_bits},
+    occurrence::occurrences,
+    shift::ShiftColumns,
+    trace::TraceLayout,
+};
+
+/// 子串比较前对原文与子串所做的规范化
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Normalization {
+    /// ASCII 大小写折叠：`A`..`Z` 映射为 `a`..`z`
+    pub case_fold: bool,
+    /// 全角转半角：全角 ASCII（U+FF01..U+FF5E）映射为对应的半角字符，全角空格（U+3000）映射为空格
+    pub full_width: bool,
+}
+
+/// 一条字节映射规则
+///
+/// 单字节规则把首字节映射为 `byte + delta`；三字节规则要求第二个字节等于固定值、
+/// 第三个字节落在区间内，把整个 UTF-8 序列映射为 `third + delta`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NormalizeRule {
+    /// 首字节下界
+    pub lo: u8,
+    /// 首字节上界
+    pub hi: u8,
+    /// 三字节规则的 `(第二个字节, 第三个字节下界, 第三个字节上界)`
+    pub tail: Option<(u8, u8, u8)>,
+    /// 输出字节相对被映射字节的偏移
+    pub delta: i16,
+}
+
+/// 规范化流中的一个字节
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NormalizedByte {
+    /// 来源字符在原文中的起始字节下标
+    pub offset: usize,
+    /// 所用规则的下标
+    pub rule: usize,
+    /// 规范化后的字节
+    pub byte: u8,
+}
+
+impl Normalization {
+    /// 映射表：单字节规则覆盖全部256个字节且互不相交，全角规则追加在后
+    pub fn rules(&self) -> Vec<NormalizeRule> {
+        let single = |lo, hi, delta| NormalizeRule {
+            lo,
+            hi,
+            tail: None,
+            delta,
+        };
+        let triple = |first, second, lo, hi, delta| NormalizeRule {
+            lo: first,
+            hi: first,
+            tail: Some((second, lo, hi)),
+            delta,
+        };
+        let fold = if self.case_fold { 0x20 } else { 0 };
+
+        let mut rules = vec![
+            single(0x00, 0x40, 0),
+            single(0x41, 0x5A, fold),
+            single(0x5B, 0xFF, 0),
+        ];
+        if self.full_width {
+            rules.extend([
+                // ！..＠ → !..@
+                triple(0xEF, 0xBC, 0x81, 0xA0, -0x60),
+                // Ａ..Ｚ → A..Z（大小写折叠时为 a..z）
+                triple(0xEF, 0xBC, 0xA1, 0xBA, fold - 0x60),
+                // ［..＿ → [.._
+                triple(0xEF, 0xBC, 0xBB, 0xBF, -0x60),
+                // ｀..～ → `..~
+                triple(0xEF, 0xBD, 0x80, 0x9E, -0x20),
+                // 全角空格 → 空格
+                triple(0xE3, 0x80, 0x80, 0x80, -0x60),
+            ]);
+        }
+        rules
+    }
+
+    /// 规范化一段字节串（用于公开子串）
+    pub fn apply(&self, bytes: &[u8]) -> Vec<u8> {
+        normalize(bytes, &self.rules())
+            .into_iter()
+            .map(|normalized| normalized.byte)
+            .collect()
+    }
+}
+
+impl NormalizeRule {
+    /// 规则读入的字节数
+    pub fn width(&self) -> usize {
+        if self.tail.is_some() { 3 } else { 1 }
+    }
+
+    /// 规则作用在 `bytes` 开头时的输出字节
+    fn apply(&self, bytes: &[u8]) -> Option<u8> {
+        let first = *bytes.first()?;
+        if first < self.lo || first > self.hi {
+            return None;
+        }
+        let mapped = match self.tail {
+            None => first,
+            Some((second, lo, hi)) => {
+                let (&b1, &b2) = (bytes.get(1)?, bytes.get(2)?);
+                if b1 != second || b2 < lo || b2 > hi {
+                    return None;
+                }
+                b2
+            }
+        };
+        Some((i16::from(mapped) + self.delta) as u8)
+    }
+}
+
+/// 按映射表从左到右规范化，三字节规则优先于单字节规则
+pub fn normalize(bytes: &[u8], rules: &[NormalizeRule]) -> Vec<NormalizedByte> {
+    let mut normalized = Vec::with_capacity(bytes.len());
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (rule, byte) = rules
+            .iter()
+            .enumerate()
+            .filter_map(|(r, rule)| rule.apply(&bytes[offset ..]).map(|byte| (r, byte)))
+            .max_by_key(|&(r, _)| rules[r].width())
+            .expect("single-byte rules cover every byte");
+        normalized.push(NormalizedByte { offset, rule, byte });
+        offset += rules[rule].width();
+    }
+    normalized
+}
+
+/// 规范化后的 `value` 在规范化后的原文中每次出现的起点（原文字节下标）
+pub fn normalized_occurrences(
+    plaintext: &[u8],
+    value: &[u8],
+    rules: &[NormalizeRule],
+) -> Vec<usize> {
+    let stream = normalize(plaintext, rules);
+    let bytes: Vec<u8> = stream.iter().map(|normalized| normalized.byte).collect();
+    occurrences(&bytes, value)
+        .map(|index| stream[index].offset)
+        .collect()
+}
+
+/// 规范化子电路的列布局
+///
+/// 每个原文行要么是某条规则的起点（规则选择子 one-hot），要么是三字节序列的第2、3个字节
+/// （续字节标志）。移位列让起点行看到其后两个字节，首字节与第三个字节是否落在规则区间内
+/// 由 `byte - lo` 与 `hi - byte` 的8位分解保证，规范化列即选中规则的输出。
+///
+/// 贪心匹配只在链下使用：电路允许证明者把全角序列按单字节原样保留，
+/// 但公开子串本身是规范化后的合法 UTF-8，不含全角序列也不以不完整的序列开头或结尾，
+/// 原样保留的全角字节无法与之对齐，因此不影响匹配结果
+#[derive(Debug, Clone)]
+pub struct NormalizerLayout {
+    /// 被规范化比较的窗口在 [`TraceLayout::windows`] 中的下标
+    pub window: usize,
+    /// 映射表（作为常量写进约束）
+    pub rules: Vec<NormalizeRule>,
+    /// 移位列（3列，第 k 行保存第 k..k+3 行的原文字节）
+    pub shift: ShiftColumns,
+    /// 规则选择子（每条规则一列）
+    pub rule_sel_cols: Range<usize>,
+    /// 首字节 `byte - lo` 的8位分解
+    pub lower_bits: Range<usize>,
+    /// 首字节 `hi - byte` 的8位分解
+    pub upper_bits: Range<usize>,
+    /// 三字节规则第三个字节 `byte - lo` 的8位分解
+    pub tail_lower_bits: Range<usize>,
+    /// 三字节规则第三个字节 `hi - byte` 的8位分解
+    pub tail_upper_bits: Range<usize>,
+    /// 续字节标志（2列：三字节序列的第2个与第3个字节）
+    pub continuation_cols: Range<usize>,
+    /// 规范化后的字节
+    pub normalized_col: usize,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+impl NormalizerLayout {
+    /// 从第 `start` 列开始依次分配子电路的列
+    pub fn new(start: usize, window: usize, normalization: Normalization) -> Self {
+        let rules = normalization.rules();
+        let mut next = start;
+        let mut alloc = |n: usize| {
+            let cols = next .. next + n;
+            next += n;
+            cols
+        };
+
+        Self {
+            window,
+            shift: ShiftColumns::new(alloc(3)),
+            rule_sel_cols: alloc(rules.len()),
+            lower_bits: alloc(8),
+            upper_bits: alloc(8),
+            tail_lower_bits: alloc(8),
+            tail_upper_bits: alloc(8),
+            continuation_cols: alloc(2),
+            normalized_col: alloc(1).start,
+            end: alloc(0).start,
+            rules,
+        }
+    }
+
+    /// 本行是否为某条规则的起点（规范化流中的一个字节）
+    pub fn emits<AB: AirBuilder>(&self, row: &[AB::Var]) -> AB::Expr {
+        self.rule_sel_cols.clone().map(|col| row[col].into()).sum()
+    }
+
+    /// 本行是否为三字节序列的续字节
+    pub fn continues<AB: AirBuilder>(&self, row: &[AB::Var]) -> AB::Expr {
+        self.continuation_cols
+            .clone()
+            .map(|col| row[col].into())
+            .sum()
+    }
+
+    /// 填充子电路的 trace 列，返回原文的规范化流
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        plaintext: &[u8],
+    ) -> Vec<NormalizedByte> {
+        self.shift.generate_trace(trace, plaintext);
+
+        let stream = normalize(plaintext, &self.rules);
+        for normalized in &stream {
+            let row = normalized.offset;
+            let rule = &self.rules[normalized.rule];
+            let first = plaintext[row];
+            trace[self.rule_sel_cols.start + normalized.rule][row] = F::ONE;
+            trace[self.normalized_col][row] = F::from_u8(normalized.byte);
+            write_bits(trace, &self.lower_bits, row, u64::from(first - rule.lo));
+            write_bits(trace, &self.upper_bits, row, u64::from(rule.hi - first));
+            if let Some((_, lo, hi)) = rule.tail {
+                let third = plaintext[row + 2];
+                write_bits(trace, &self.tail_lower_bits, row, u64::from(third - lo));
+                write_bits(trace, &self.tail_upper_bits, row, u64::from(hi - third));
+                trace[self.continuation_cols.start][row + 1] = F::ONE;
+                trace[self.continuation_cols.start + 1][row + 2] = F::ONE;
+            }
+        }
+
+        stream
+    }
+
+    /// 规范化约束
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+    ) {
+        let shift = |row: &[AB::Var], j: usize| row[self.shift.cols.start + j];
+        let sel = |r: usize| local[self.rule_sel_cols.start + r];
+        let (second, third) = (
+            local[self.continuation_cols.start],
+            local[self.continuation_cols.start + 1],
+        );
+
+        self.shift.eval(builder, local, next, layout);
+
+        // 每个原文行要么是规则起点，要么是续字节
+        for col in self
+            .rule_sel_cols
+            .clone()
+            .chain(self.continuation_cols.clone())
+        {
+            builder.assert_bool(local[col]);
+        }
+        builder.assert_eq(
+            self.emits::<AB>(local) + self.continues::<AB>(local),
+            local[layout.sha256.is_data_col],
+        );
+
+        // 三字节规则之后的两行是续字节
+        let mut starts_triple = AB::Expr::ZERO;
+        for (r, rule) in self.rules.iter().enumerate() {
+            if rule.tail.is_some() {
+                starts_triple += sel(r).into();
+            }
+        }
+        builder.when_first_row().assert_zero(second);
+        builder.when_first_row().assert_zero(third);
+        builder
+            .when_transition()
+            .assert_eq(next[self.continuation_cols.start], starts_triple);
+        builder
+            .when_transition()
+            .assert_eq(next[self.continuation_cols.start + 1], second);
+
+        // 首字节落在选中规则的区间内，三字节规则另检查第二个字节与第三个字节
+        let mut lower = AB::Expr::ZERO;
+        let mut upper = AB::Expr::ZERO;
+        let mut second_diff = AB::Expr::ZERO;
+        let mut tail_lower = AB::Expr::ZERO;
+        let mut tail_upper = AB::Expr::ZERO;
+        let mut normalized = AB::Expr::ZERO;
+        for (r, rule) in self.rules.iter().enumerate() {
+            let byte = |value: u8| AB::Expr::from_u8(value);
+            lower += sel(r) * (shift(local, 0) - byte(rule.lo));
+            upper += sel(r) * (byte(rule.hi) - shift(local, 0));
+            let mapped = match rule.tail {
+                None => shift(local, 0),
+                Some((b1, lo, hi)) => {
+                    second_diff += sel(r) * (shift(local, 1) - byte(b1));
+                    tail_lower += sel(r) * (shift(local, 2) - byte(lo));
+                    tail_upper += sel(r) * (byte(hi) - shift(local, 2));
+                    shift(local, 2)
+                }
+            };
+            normalized += sel(r) * offset_by::<AB>(mapped.into(), rule.delta);
+        }
+        for col in self
+            .lower_bits
+            .clone()
+            .chain(self.upper_bits.clone())
+            .chain(self.tail_lower_bits.clone())
+            .chain(self.tail_upper_bits.clone())
+        {
+            builder.assert_bool(local[col]);
+        }
+        builder.assert_eq(lower, pack::<AB>(&local[self.lower_bits.clone()]));
+        builder.assert_eq(upper, pack::<AB>(&local[self.upper_bits.clone()]));
+        builder.assert_zero(second_diff);
+        builder.assert_eq(tail_lower, pack::<AB>(&local[self.tail_lower_bits.clone()]));
+        builder.assert_eq(tail_upper, pack::<AB>(&local[self.tail_upper_bits.clone()]));
+
+        // 规范化列等于选中规则的输出
+        builder.assert_eq(local[self.normalized_col], normalized);
+    }
+}
+
+/// `value + delta`
+fn offset_by<AB: AirBuilder>(value: AB::Expr, delta: i16) -> AB::Expr {
+    let magnitude = AB::Expr::from_u16(delta.unsigned_abs());
+    if delta < 0 {
+        value - magnitude
+    } else {
+        value + magnitude
+    }
+}
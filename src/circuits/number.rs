@@ -0,0 +1,222 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+use crate::{
+    circuits::{
+        bits::{pack, write_bits},
+        trace::{TraceLayout, WindowLayout},
+    },
+    error::ZkpError,
+};
+
+/// 数值位宽上限：数值以及它与上下界之差都必须能分解为这么多位
+///
+/// 累加值每一步都小于 2⁶⁰，`acc·10 + digit` 因而小于 Goldilocks 模数，不会回绕
+pub const VALUE_BITS: usize = 60;
+
+/// 十进制数解析与区间比较子电路的列布局
+///
+/// 数值紧跟在某个匹配窗口（公开标签）之后：标签窗口最后一行的下一行起是一段连续的 ASCII 数字，
+/// 数字段之后的第一个字节不是数字，因此证明者不能只截取数值的一部分。
+/// 累加列逐行计算 `acc' = acc·10 + digit`，最后一行的累加值即为数值；
+/// `value - min` 与 `max - value` 都分解为 [`VALUE_BITS`] 位，证明 `min ≤ value ≤ max`。
+#[derive(Debug, Clone)]
+pub struct NumberLayout {
+    /// 标签所在窗口的下标
+    pub window: usize,
+    /// 本行消息字节是否为 ASCII 数字（由字节位分解计算）
+    pub is_digit_col: usize,
+    /// 本行是否属于数字段
+    pub digit_flag_col: usize,
+    /// 数值累加列
+    pub value_col: usize,
+    /// 累加值的位分解（每一行都小于 2^VALUE_BITS）
+    pub value_bits: Range<usize>,
+    /// `value - min` 的位分解（最后一行）
+    pub lower_bits: Range<usize>,
+    /// `max - value` 的位分解（最后一行）
+    pub upper_bits: Range<usize>,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+impl NumberLayout {
+    /// 从第 `start` 列开始依次分配子电路的列，标签为第 `window` 个窗口
+    pub fn new(start: usize, window: usize) -> Self {
+        let mut next = start;
+        let mut alloc = |n: usize| {
+            let cols = next .. next + n;
+            next += n;
+            cols
+        };
+
+        Self {
+            window,
+            is_digit_col: alloc(1).start,
+            digit_flag_col: alloc(1).start,
+            value_col: alloc(1).start,
+            value_bits: alloc(VALUE_BITS),
+            lower_bits: alloc(VALUE_BITS),
+            upper_bits: alloc(VALUE_BITS),
+            end: alloc(0).start,
+        }
+    }
+
+    /// 填充子电路的 trace 列
+    ///
+    /// `message` 为填充后的消息字节，`start` 为数字段的第一行（标签窗口之后）
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        message: &[u8],
+        plaintext_len: usize,
+        start: usize,
+        bounds: (u64, u64),
+    ) -> Result<(), ZkpError> {
+        let (value, digits) = read_number(&message[.. plaintext_len], start).ok_or(
+            ZkpError::InvalidWitness("No decimal number after the label".to_string()),
+        )?;
+        let (min, max) = bounds;
+        if value < min || value > max {
+            return Err(ZkpError::InvalidWitness(format!(
+                "Value {} is outside [{}, {}]",
+                value, min, max
+            )));
+        }
+
+        for (row, byte) in message.iter().enumerate() {
+            trace[self.is_digit_col][row] = F::from_bool(byte.is_ascii_digit());
+        }
+
+        let height = trace[0].len();
+        let mut acc = 0u64;
+        for row in 0 .. height {
+            if (start .. start + digits).contains(&row) {
+                trace[self.digit_flag_col][row] = F::ONE;
+                acc = acc * 10 + u64::from(message[row] - b'0');
+            }
+            trace[self.value_col][row] = F::from_u64(acc);
+            write_bits(trace, &self.value_bits, row, acc);
+        }
+
+        let last = height - 1;
+        write_bits(trace, &self.lower_bits, last, value - min);
+        write_bits(trace, &self.upper_bits, last, max - value);
+
+        Ok(())
+    }
+
+    /// 十进制数解析与区间比较约束
+    ///
+    /// `window` 为公开标签所在的窗口，`bounds` 为公开的上下界 `[min, max]`
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+        window: &WindowLayout,
+        bounds: &[AB::Expr],
+    ) {
+        let bit = |i: usize| local[layout.range_bits_cols.start + i];
+        let is_digit = local[self.is_digit_col];
+        let flag = local[self.digit_flag_col];
+        let next_flag = next[self.digit_flag_col];
+        let value = local[self.value_col];
+
+        // ASCII 数字 0x30..=0x39：高4位为 0011，低4位不超过9（b3 = 1 时 b2 = b1 = 0）
+        let high = (AB::Expr::ONE - bit(7)) * (AB::Expr::ONE - bit(6)) * bit(5) * bit(4);
+        let low = (AB::Expr::ONE - bit(3) * bit(2)) * (AB::Expr::ONE - bit(3) * bit(1));
+        builder.assert_eq(is_digit, high * low);
+
+        // 数字段只落在原文的数字字节上
+        builder.assert_bool(flag);
+        builder.when(flag).assert_one(is_digit);
+        builder
+            .when(flag)
+            .assert_one(local[layout.sha256.is_data_col]);
+        builder.when_first_row().assert_zero(flag);
+        builder.when_first_row().assert_zero(value);
+
+        let in_window = local[window.offset_indicator_col];
+        let next_in_window = next[window.offset_indicator_col];
+        let mut transition = builder.when_transition();
+
+        // 标签窗口结束后的下一行开始数字段，数字段也只能从那里开始
+        transition
+            .when(in_window)
+            .when(AB::Expr::ONE - next_in_window)
+            .assert_one(next_flag);
+        transition
+            .when(next_flag)
+            .when(AB::Expr::ONE - flag)
+            .assert_zero(AB::Expr::ONE - in_window + next_in_window);
+
+        // 数字段取到最长：之后的第一个字节不是数字
+        transition
+            .when(flag)
+            .when(AB::Expr::ONE - next_flag)
+            .assert_zero(next[self.is_digit_col]);
+
+        // acc' = acc + flag' · (9·acc + digit')，即数字段内 acc' = 10·acc + digit'
+        let next_digit = next[layout.plaintext_col] - AB::Expr::from_u8(b'0');
+        transition.assert_eq(
+            next[self.value_col],
+            value + next_flag * (value * AB::Expr::from_u8(9) + next_digit),
+        );
+
+        // 累加值逐行小于 2^VALUE_BITS
+        for col in self.value_bits.clone() {
+            builder.assert_bool(local[col]);
+        }
+        builder.assert_eq(value, pack::<AB>(&local[self.value_bits.clone()]));
+
+        // min ≤ value ≤ max：两个差值都能分解为 VALUE_BITS 位
+        for col in self.lower_bits.clone().chain(self.upper_bits.clone()) {
+            builder.assert_bool(local[col]);
+        }
+        let (min, max) = (bounds[0].clone(), bounds[1].clone());
+        let mut when_last = builder.when_last_row();
+        when_last.assert_eq(value - min, pack::<AB>(&local[self.lower_bits.clone()]));
+        when_last.assert_eq(max - value, pack::<AB>(&local[self.upper_bits.clone()]));
+    }
+}
+
+/// 从 `start` 处读取一段最长的 ASCII 十进制数，返回数值与位数
+///
+/// 没有数字或数值不小于 2^VALUE_BITS 时返回 `None`
+pub fn read_number(plaintext: &[u8], start: usize) -> Option<(u64, usize)> {
+    let digits = plaintext
+        .get(start ..)?
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    if digits == 0 {
+        return None;
+    }
+
+    plaintext[start .. start + digits]
+        .iter()
+        .try_fold(0u64, |acc, &byte| {
+            acc.checked_mul(10)?
+                .checked_add(u64::from(byte - b'0'))
+                .filter(|&value| value < 1 << VALUE_BITS)
+        })
+        .map(|value| (value, digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_number() {
+        assert_eq!(read_number(b"PLT 250 k", 4), Some((250, 3)));
+        assert_eq!(read_number(b"PLT 250", 4), Some((250, 3)));
+        assert_eq!(read_number(b"PLT x", 4), None);
+        assert_eq!(read_number(b"PLT ", 4), None);
+        assert_eq!(read_number(b"99999999999999999999", 0), None);
+    }
+}
@@ -0,0 +1,235 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+use crate::{
+    circuits::{
+        bits::{pack, write_bits},
+        shift::MismatchLayout,
+        trace::TraceLayout,
+    },
+    error::ZkpError,
+};
+
+/// 出现次数上下界比较所用的位数，次数区间的上界必须小于 2^COUNT_BITS
+pub const COUNT_BITS: usize = 32;
+
+/// `value` 在 `plaintext` 中每次出现的起始字节下标（按字节比较，允许重叠；空串不出现）
+pub fn occurrences<'a>(plaintext: &'a [u8], value: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    plaintext
+        .windows(value.len().max(1))
+        .enumerate()
+        .filter(move |&(_, window)| window == value)
+        .map(|(offset, _)| offset)
+}
+
+/// 与 [`occurrences`] 相同，但 `hidden[j]` 为真的位置可以是任意字节
+pub fn masked_occurrences<'a>(
+    plaintext: &'a [u8],
+    value: &'a [u8],
+    hidden: &'a [bool],
+) -> impl Iterator<Item = usize> + 'a {
+    plaintext
+        .windows(value.len().max(1))
+        .enumerate()
+        .filter(move |&(_, window)| {
+            window.len() == value.len()
+                && (0 .. value.len()).all(|j| hidden.get(j) == Some(&true) || window[j] == value[j])
+        })
+        .map(|(offset, _)| offset)
+}
+
+/// 子串出现计数子电路的列布局
+///
+/// 与不出现子电路共用移位列与失配见证（见 [`MismatchLayout`]）。
+/// 每一行有一个出现标志：为1时从该行开始的窗口逐字节等于 `t`，
+/// 为0时给出失配见证。计数列是出现标志的前缀和，
+/// 因此第 k 行的计数就是起点不晚于 k 的出现次数（重叠的出现分别计数）
+#[derive(Debug, Clone)]
+pub struct OccurrenceLayout {
+    /// 移位列与失配见证（未出现的行）
+    pub mismatch: MismatchLayout,
+    /// 出现标志（0/1）
+    pub match_col: usize,
+    /// 出现次数的前缀和
+    pub count_col: usize,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+/// 第 n 次出现命题：窗口的起点是其子串的第 n 次出现
+#[derive(Debug, Clone)]
+pub struct NthOccurrenceLayout {
+    /// 窗口在 [`TraceLayout::windows`] 中的下标
+    pub window: usize,
+    /// 对该窗口子串计数的子电路
+    pub occurrence: OccurrenceLayout,
+}
+
+/// 出现次数命题：子串的出现总数落在公开区间 `[min, max]` 内
+///
+/// 计数列在最后一行的值就是出现总数，`count - min` 与 `max - count` 都分解为 [`COUNT_BITS`] 位
+#[derive(Debug, Clone)]
+pub struct CountLayout {
+    /// 对公开子串计数的子电路
+    pub occurrence: OccurrenceLayout,
+    /// `count - min` 的位分解（仅最后一行）
+    pub lower_bits: Range<usize>,
+    /// `max - count` 的位分解（仅最后一行）
+    pub upper_bits: Range<usize>,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+impl OccurrenceLayout {
+    /// 从第 `start` 列开始依次分配子电路的列
+    pub fn new(start: usize, max_substring_len: usize) -> Self {
+        let mismatch = MismatchLayout::new(start, max_substring_len);
+
+        Self {
+            match_col: mismatch.end,
+            count_col: mismatch.end + 1,
+            end: mismatch.end + 2,
+            mismatch,
+        }
+    }
+
+    /// 填充子电路的 trace 列
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        plaintext: &[u8],
+        value: &[u8],
+    ) -> Result<(), ZkpError> {
+        if value.is_empty() {
+            return Err(ZkpError::InvalidPublicInput(
+                "Cannot count occurrences of an empty string".to_string(),
+            ));
+        }
+
+        let matches = self.mismatch.generate_trace(trace, plaintext, value);
+        let mut count = 0;
+        for (row, matched) in matches.into_iter().enumerate() {
+            if matched {
+                trace[self.match_col][row] = F::ONE;
+                count += 1;
+            }
+            trace[self.count_col][row] = F::from_u32(count);
+        }
+
+        Ok(())
+    }
+
+    /// 出现计数约束
+    ///
+    /// `string_pv` 为公开值中被计数字符串的 `[|t|, t[0..max], mask[0..max]]`
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+        string_pv: &[AB::Expr],
+    ) {
+        let max_substring_len = self.mismatch.sel_cols.len();
+        let bytes = &string_pv[1 .. 1 + max_substring_len];
+        let mask = &string_pv[1 + max_substring_len .. 1 + 2 * max_substring_len];
+        let shift = |j: usize| local[self.mismatch.shift.cols.start + j];
+        let matched = local[self.match_col];
+
+        // 出现：t 长度之内的每个字节都相等
+        builder.assert_bool(matched);
+        for j in 0 .. max_substring_len {
+            builder
+                .when(matched)
+                .when(mask[j].clone())
+                .assert_eq(shift(j), bytes[j].clone());
+        }
+
+        // 未出现：失配见证
+        self.mismatch.eval(
+            builder,
+            local,
+            next,
+            layout,
+            string_pv,
+            AB::Expr::ONE - matched,
+        );
+
+        // 计数：出现标志的前缀和
+        let count = local[self.count_col];
+        builder.when_first_row().assert_eq(count, matched);
+        builder
+            .when_transition()
+            .assert_eq(next[self.count_col], count + next[self.match_col]);
+    }
+}
+
+impl CountLayout {
+    /// 从第 `start` 列开始依次分配子电路的列
+    pub fn new(start: usize, max_substring_len: usize) -> Self {
+        let occurrence = OccurrenceLayout::new(start, max_substring_len);
+        let lower_bits = occurrence.end .. occurrence.end + COUNT_BITS;
+        let upper_bits = lower_bits.end .. lower_bits.end + COUNT_BITS;
+
+        Self {
+            end: upper_bits.end,
+            occurrence,
+            lower_bits,
+            upper_bits,
+        }
+    }
+
+    /// 填充子电路的 trace 列，出现次数不在 `[min, max]` 内时返回错误
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        plaintext: &[u8],
+        value: &[u8],
+        min: usize,
+        max: usize,
+    ) -> Result<(), ZkpError> {
+        self.occurrence.generate_trace(trace, plaintext, value)?;
+
+        let count = occurrences(plaintext, value).count();
+        if count < min || count > max {
+            return Err(ZkpError::InvalidWitness(format!(
+                "Substring occurs {} times, outside [{}, {}]",
+                count, min, max
+            )));
+        }
+
+        let last = trace[0].len() - 1;
+        write_bits(trace, &self.lower_bits, last, (count - min) as u64);
+        write_bits(trace, &self.upper_bits, last, (max - count) as u64);
+
+        Ok(())
+    }
+
+    /// 出现次数约束
+    ///
+    /// `string_pv` 同 [`OccurrenceLayout::eval`]，`bounds` 为公开的上下界 `[min, max]`
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+        string_pv: &[AB::Expr],
+        bounds: &[AB::Expr],
+    ) {
+        self.occurrence
+            .eval(builder, local, next, layout, string_pv);
+
+        // min ≤ count ≤ max：两个差值都能分解为 COUNT_BITS 位
+        for col in self.lower_bits.clone().chain(self.upper_bits.clone()) {
+            builder.assert_bool(local[col]);
+        }
+        let count = local[self.occurrence.count_col];
+        let (min, max) = (bounds[0].clone(), bounds[1].clone());
+        let mut when_last = builder.when_last_row();
+        when_last.assert_eq(count - min, pack::<AB>(&local[self.lower_bits.clone()]));
+        when_last.assert_eq(max - count, pack::<AB>(&local[self.upper_bits.clone()]));
+    }
+}
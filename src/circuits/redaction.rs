@@ -0,0 +1,256 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+use crate::{circuits::trace::TraceLayout, config::RedactedDocument, hash::sha256};
+
+/// 一份涂黑文档最多的翻转边界数（每个涂黑区间有起点与终点两个边界，每个边界占一列）
+pub const MAX_REDACTION_BOUNDARIES: usize = 64;
+
+/// 指纹个数，每个指纹在一个独立的求值点上比较未涂黑的字节
+pub const FINGERPRINTS: usize = 3;
+
+/// 指纹求值点的域分隔标签
+const CHALLENGE_TAG: &[u8] = b"zkp-rs/redaction/v1";
+
+/// 掩码翻转的位置：`hidden[k] != hidden[k - 1]` 的下标 k（第0个字节之前视为未涂黑）
+pub fn boundaries(hidden: &[bool]) -> Vec<usize> {
+    (0 .. hidden.len())
+        .filter(|&k| hidden[k] != (k > 0 && hidden[k - 1]))
+        .collect()
+}
+
+/// 涂黑文档与原文等长，且每个未涂黑的字节与原文同一位置的字节相等
+pub fn derives(plaintext: &[u8], document: &RedactedDocument) -> bool {
+    plaintext.len() == document.bytes.len()
+        && document.hidden.len() == document.bytes.len()
+        && plaintext
+            .iter()
+            .zip(&document.bytes)
+            .zip(&document.hidden)
+            .all(|((&byte, &public), &hidden)| hidden || byte == public)
+}
+
+/// 指纹的求值点：由承诺与整份公开文档（含掩码）哈希得到
+///
+/// 承诺在求值点确定之前已经固定了原文，证明者无法针对求值点挑选原文
+pub fn challenges(commitment: &[u8; 32], document: &RedactedDocument) -> [u64; FINGERPRINTS] {
+    let mut data = CHALLENGE_TAG.to_vec();
+    data.extend_from_slice(commitment);
+    data.extend_from_slice(&(document.bytes.len() as u64).to_be_bytes());
+    data.extend_from_slice(&document.bytes);
+    data.extend(document.hidden.iter().map(|&hidden| u8::from(hidden)));
+    let digest = sha256::hash(&data);
+    std::array::from_fn(|i| {
+        u64::from_be_bytes(digest[8 * i .. 8 * i + 8].try_into().expect("8 bytes"))
+    })
+}
+
+/// 未涂黑字节在 `x` 处的指纹 `Σ (1 - hidden[k])·bytes[k]·x^(n-1-k)`（Horner 求值）
+pub fn fingerprint<F: Field>(bytes: &[u8], hidden: &[bool], x: F) -> F {
+    bytes
+        .iter()
+        .zip(hidden)
+        .fold(F::ZERO, |acc, (&byte, &hidden)| {
+            acc * x + if hidden { F::ZERO } else { F::from_u8(byte) }
+        })
+}
+
+/// 一份涂黑文档的公开值：`[翻转边界..., 求值点 x₀..x₂, 指纹₀..指纹₂]`
+pub fn public_values<F: Field>(commitment: &[u8; 32], document: &RedactedDocument) -> Vec<F> {
+    let challenges = challenges(commitment, document).map(F::from_u64);
+    let mut values: Vec<F> = boundaries(&document.hidden)
+        .into_iter()
+        .map(F::from_usize)
+        .collect();
+    values.extend(challenges);
+    values.extend(
+        challenges
+            .iter()
+            .map(|&x| fingerprint(&document.bytes, &document.hidden, x)),
+    );
+    values
+}
+
+/// 涂黑文档子电路的列布局
+///
+/// 涂黑文档从第0行开始逐行对应原文行，公开长度把原文行数固定为文档长度。
+/// uni-stark 0.3.0 没有预处理列，逐行变化的公开数据只能经由公开值进入约束，
+/// 因此文档字节与掩码不放进公开列，而是分两部分与原文列比较：
+///
+/// - 掩码由公开的翻转边界精确确定：边界列在首行装入公开边界，当前原文行下标 `data_count - 1`
+///   等于第一个边界时翻转标志为1（不相等时由逆元列证明差不为零），
+///   此时边界列整体左移一格，末尾补入哨兵 -1（不等于任何原文行下标）；
+///   掩码列在翻转行取反，最后一行所有边界都已用完。
+/// - 未涂黑的字节用指纹比较：每个指纹列按 Horner 法累加 `(1 - hidden)·plaintext`，
+///   最后一行等于验证者从公开文档算出的同一指纹。求值点由承诺与公开文档哈希得到，
+///   原文在此之前已被承诺固定，未涂黑字节不同而指纹全部相同的概率不超过
+///   `(max_text_len/p)^FINGERPRINTS`（Schwartz–Zippel 引理）。
+///
+/// 列数为 `边界数 + 3 + FINGERPRINTS`，与文档长度无关，边界数由 [`MAX_REDACTION_BOUNDARIES`] 限定
+#[derive(Debug, Clone)]
+pub struct RedactionLayout {
+    /// 边界寄存器（每个翻转边界一列，第一列是下一个翻转位置）
+    pub boundary_cols: Range<usize>,
+    /// 翻转标志（本行是翻转边界时为1）
+    pub toggle_col: usize,
+    /// `data_count - 1 - 第一个边界` 的逆元（不是翻转边界的原文行）
+    pub inverse_col: usize,
+    /// 本行是否被涂黑
+    pub hidden_col: usize,
+    /// 指纹累加列（每个求值点一列）
+    pub fingerprint_cols: Range<usize>,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+impl RedactionLayout {
+    /// 从第 `start` 列开始依次分配子电路的列，`boundaries` 为掩码的翻转边界数
+    pub fn new(start: usize, boundaries: usize) -> Self {
+        let toggle_col = start + boundaries;
+        let fingerprint_cols = toggle_col + 3 .. toggle_col + 3 + FINGERPRINTS;
+
+        Self {
+            boundary_cols: start .. toggle_col,
+            toggle_col,
+            inverse_col: toggle_col + 1,
+            hidden_col: toggle_col + 2,
+            end: fingerprint_cols.end,
+            fingerprint_cols,
+        }
+    }
+
+    /// 子电路占用的公开值个数
+    pub fn num_public_values(&self) -> usize {
+        self.boundary_cols.len() + 2 * FINGERPRINTS
+    }
+
+    /// 约束掩码与未涂黑字节的指纹，`pis` 为 [`public_values`] 给出的这份文档的公开值
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+        pis: &[AB::Expr],
+    ) {
+        let sha256 = &layout.sha256;
+        let is_data = local[sha256.is_data_col];
+        let toggle = local[self.toggle_col];
+        let hidden: AB::Expr = local[self.hidden_col].into();
+        let (boundaries, rest) = pis.split_at(self.boundary_cols.len());
+        let (challenges, fingerprints) = rest.split_at(FINGERPRINTS);
+
+        // 边界寄存器：首行装入公开边界，翻转行左移一格并补入哨兵
+        let register = |row: &[AB::Var], i: usize| -> AB::Expr {
+            self.boundary_cols
+                .clone()
+                .nth(i)
+                .map_or(AB::Expr::NEG_ONE, |col| row[col].into())
+        };
+        for (i, boundary) in boundaries.iter().enumerate() {
+            builder
+                .when_first_row()
+                .assert_eq(register(local, i), boundary.clone());
+            builder.when_transition().assert_eq(
+                register(next, i),
+                register(local, i) + (register(local, i + 1) - register(local, i)) * toggle,
+            );
+        }
+        if !boundaries.is_empty() {
+            builder
+                .when_last_row()
+                .assert_eq(register(local, 0), AB::Expr::NEG_ONE);
+        }
+
+        // 翻转标志：原文行下标等于第一个边界时为1，否则为0
+        let data_count: AB::Expr = local[sha256.data_count_col].into();
+        let diff = data_count - AB::Expr::ONE - register(local, 0);
+        builder.assert_zero(diff.clone() * toggle);
+        builder
+            .when(is_data)
+            .when(AB::Expr::ONE - toggle)
+            .assert_one(diff * local[self.inverse_col]);
+        builder.when(AB::Expr::ONE - is_data).assert_zero(toggle);
+
+        // 掩码列：首行等于翻转标志，之后在翻转行取反
+        let next_toggle: AB::Expr = next[self.toggle_col].into();
+        builder.when_first_row().assert_eq(hidden.clone(), toggle);
+        builder.when_transition().assert_eq(
+            next[self.hidden_col],
+            hidden.clone() + next_toggle.clone() - hidden * next_toggle.double(),
+        );
+
+        // 指纹：未涂黑的原文字节按 Horner 法累加，原文行之外保持不变
+        let visible =
+            |row: &[AB::Var]| (AB::Expr::ONE - row[self.hidden_col]) * row[layout.plaintext_col];
+        let next_is_data = next[sha256.is_data_col];
+        let points = self
+            .fingerprint_cols
+            .clone()
+            .zip(challenges)
+            .zip(fingerprints);
+        for ((col, x), expected) in points {
+            let acc: AB::Expr = local[col].into();
+            builder
+                .when_first_row()
+                .assert_eq(acc.clone(), visible(local));
+            builder.when_transition().assert_eq(
+                next[col],
+                acc.clone()
+                    + acc.clone() * (x.clone() - AB::Expr::ONE) * next_is_data
+                    + visible(next),
+            );
+            builder.when_last_row().assert_eq(acc, expected.clone());
+        }
+    }
+
+    /// 填充子电路的 trace 列
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        plaintext: &[u8],
+        commitment: &[u8; 32],
+        document: &RedactedDocument,
+    ) {
+        let challenges = challenges(commitment, document).map(F::from_u64);
+        let mut register = boundaries(&document.hidden);
+        let mut hidden = false;
+        let mut fingerprints = [F::ZERO; FINGERPRINTS];
+
+        for row in 0 .. trace[0].len() {
+            for (i, col) in self.boundary_cols.clone().enumerate() {
+                trace[col][row] = register.get(i).map_or(F::NEG_ONE, |&k| F::from_usize(k));
+            }
+
+            let byte = plaintext.get(row);
+            if byte.is_some() {
+                if register.first() == Some(&row) {
+                    register.remove(0);
+                    hidden = !hidden;
+                    trace[self.toggle_col][row] = F::ONE;
+                } else {
+                    let boundary = register.first().map_or(F::NEG_ONE, |&k| F::from_usize(k));
+                    trace[self.inverse_col][row] = (F::from_usize(row) - boundary).inverse();
+                }
+            }
+            trace[self.hidden_col][row] = F::from_bool(hidden);
+
+            let visible = match byte {
+                Some(&byte) if !hidden => F::from_u8(byte),
+                _ => F::ZERO,
+            };
+            for (col, (acc, &x)) in self
+                .fingerprint_cols
+                .clone()
+                .zip(fingerprints.iter_mut().zip(&challenges))
+            {
+                if byte.is_some() {
+                    *acc = *acc * x + visible;
+                }
+                trace[col][row] = *acc;
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+use crate::{
+    circuits::{
+        bits::{pack, write_bits},
+        dfa::{Dfa, DfaEdge},
+        trace::TraceLayout,
+    },
+    error::ZkpError,
+};
+
+/// 正则匹配子电路的列布局
+///
+/// DFA 的转移表在电路建立时由公开的模式编译得到，作为常量写进约束多项式，
+/// 证明者与验证者由同一模式得到同一张表。这是选择子编码而不是查找论证：
+/// 每条边占一列 one-hot 选择子，每个原文行选中一条边 `(from, [lo, hi], to)`：
+/// 选中的边必须从上一行的状态出发、覆盖本行的原文字节，
+/// 本行的状态即为边的终点；最后一行的状态必须是接受状态。
+/// 列数随边数线性增长，由 [`crate::circuits::dfa::MAX_DFA_EDGES`] 限定。
+/// 字节落在 `[lo, hi]` 内由 `byte - lo` 与 `hi - byte` 的8位分解保证
+#[derive(Debug, Clone)]
+pub struct RegexLayout {
+    /// 由模式编译出的 DFA
+    pub dfa: Dfa,
+    /// 读入本行字节之后的 DFA 状态（原文之后保持不变）
+    pub state_col: usize,
+    /// 边选择子（每条边一列，原文行上恰有一列为1）
+    pub edge_sel_cols: Range<usize>,
+    /// `byte - lo` 的8位分解
+    pub lower_bits: Range<usize>,
+    /// `hi - byte` 的8位分解
+    pub upper_bits: Range<usize>,
+    /// 接受状态选择子（每个接受状态一列，仅最后一行恰有一列为1）
+    pub accept_sel_cols: Range<usize>,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+impl RegexLayout {
+    /// 从第 `start` 列开始依次分配子电路的列
+    pub fn new(start: usize, dfa: Dfa) -> Self {
+        let mut next = start;
+        let mut alloc = |n: usize| {
+            let cols = next .. next + n;
+            next += n;
+            cols
+        };
+
+        Self {
+            state_col: alloc(1).start,
+            edge_sel_cols: alloc(dfa.edges.len()),
+            lower_bits: alloc(8),
+            upper_bits: alloc(8),
+            accept_sel_cols: alloc(dfa.accepting.len()),
+            end: alloc(0).start,
+            dfa,
+        }
+    }
+
+    /// 填充子电路的 trace 列：在原文上运行 DFA 并记录每一步走过的边
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        plaintext: &[u8],
+    ) -> Result<(), ZkpError> {
+        let path = self.dfa.run(plaintext).ok_or(ZkpError::InvalidWitness(
+            "Plaintext does not match the pattern".to_string(),
+        ))?;
+
+        let height = trace[0].len();
+        let mut state = 0;
+        for row in 0 .. height {
+            if let Some(&edge_index) = path.get(row) {
+                let edge = &self.dfa.edges[edge_index];
+                let byte = plaintext[row];
+                trace[self.edge_sel_cols.start + edge_index][row] = F::ONE;
+                write_bits(trace, &self.lower_bits, row, u64::from(byte - edge.lo));
+                write_bits(trace, &self.upper_bits, row, u64::from(edge.hi - byte));
+                state = edge.to;
+            }
+            trace[self.state_col][row] = F::from_u32(state);
+        }
+
+        let accept_index = self
+            .dfa
+            .accepting
+            .iter()
+            .position(|&accepting| accepting == state)
+            .ok_or(ZkpError::InvalidWitness(
+                "Plaintext does not match the pattern".to_string(),
+            ))?;
+        trace[self.accept_sel_cols.start + accept_index][height - 1] = F::ONE;
+
+        Ok(())
+    }
+
+    /// 正则匹配约束
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+    ) {
+        let is_data = local[layout.sha256.is_data_col];
+        let next_is_data = next[layout.sha256.is_data_col];
+        let state = local[self.state_col];
+
+        // 选中边的字段：Σ_e sel_e · field(edge_e)
+        let selected = |row: &[AB::Var], field: fn(&DfaEdge) -> u32| -> AB::Expr {
+            self.edge_sel_cols
+                .clone()
+                .zip(&self.dfa.edges)
+                .map(|(col, edge)| row[col] * AB::Expr::from_u32(field(edge)))
+                .sum()
+        };
+
+        // 原文行上恰好选中一条边，其余行不选
+        let mut selected = AB::Expr::ZERO;
+        for col in self.edge_sel_cols.clone() {
+            builder.assert_bool(local[col]);
+            selected += local[col].into();
+        }
+        builder.assert_eq(selected, is_data);
+
+        // 原文字节落在选中边的字节区间内：lo ≤ byte ≤ hi
+        let byte = local[layout.plaintext_col];
+        for col in self.lower_bits.clone().chain(self.upper_bits.clone()) {
+            builder.assert_bool(local[col]);
+        }
+        builder.assert_eq(
+            byte - selected(local, |edge| edge.lo.into()),
+            pack::<AB>(&local[self.lower_bits.clone()]),
+        );
+        builder.assert_eq(
+            selected(local, |edge| edge.hi.into()) - byte,
+            pack::<AB>(&local[self.upper_bits.clone()]),
+        );
+
+        // 首行从初始状态0出发
+        builder
+            .when_first_row()
+            .assert_zero(selected(local, |edge| edge.from));
+        builder
+            .when_first_row()
+            .assert_eq(state, selected(local, |edge| edge.to));
+
+        // 下一条边从本行状态出发；原文之后状态保持不变
+        let mut transition = builder.when_transition();
+        transition.assert_eq(selected(next, |edge| edge.from), next_is_data * state);
+        transition.assert_eq(
+            next[self.state_col],
+            selected(next, |edge| edge.to) + (AB::Expr::ONE - next_is_data) * state,
+        );
+
+        // 最后一行的状态是某个接受状态
+        let mut accepted = AB::Expr::ZERO;
+        let mut accept_state = AB::Expr::ZERO;
+        for (col, &accepting) in self.accept_sel_cols.clone().zip(&self.dfa.accepting) {
+            builder.assert_bool(local[col]);
+            accepted += local[col].into();
+            accept_state += local[col] * AB::Expr::from_u32(accepting);
+        }
+        let mut when_last = builder.when_last_row();
+        when_last.assert_one(accepted);
+        when_last.assert_eq(state, accept_state);
+    }
+}
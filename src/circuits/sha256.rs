@@ -0,0 +1,592 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+use crate::{
+    circuits::bits::{pack, write_bits},
+    error::ZkpError,
+    hash::sha256::{self, IV, K, ROUNDS},
+};
+
+/// 一个字的位数
+const WORD_BITS: usize = 32;
+
+/// SHA-256 压缩函数子电路的列布局
+///
+/// 每个压缩轮占一行：第 t 行保存第 t 轮开始时的工作变量与消息调度窗口，
+/// 下一行保存该轮的输出。最后一轮之后的一行（摘要行）起，链接值列恒等于最终摘要。
+///
+/// 参与位运算（Σ、Ch、Maj）的工作变量 a, b, c, e, f, g 以32个布尔列按小端存储；
+/// d 与 h 只参与加法，以单列字存储。
+///
+/// 多个块首尾相接：第 b 块占据第 `64b .. 64b + 64` 行，块的最后一轮把链接值加到输出上，
+/// 下一行即为下一块的第0轮。第 r 行同时携带填充后消息的第 r 个字节，
+/// 消息填充（`0x80`、补零与64位长度）在电路内逐字节约束。
+#[derive(Debug, Clone)]
+pub struct Sha256Layout {
+    /// 轮次选择子（64列 one-hot，第 t 列为1表示本行执行第 t 轮）
+    pub round_cols: Range<usize>,
+    /// 工作变量 a 的位分解
+    pub a_bits: Range<usize>,
+    /// 工作变量 b 的位分解
+    pub b_bits: Range<usize>,
+    /// 工作变量 c 的位分解
+    pub c_bits: Range<usize>,
+    /// 工作变量 d
+    pub d_col: usize,
+    /// 工作变量 e 的位分解
+    pub e_bits: Range<usize>,
+    /// 工作变量 f 的位分解
+    pub f_bits: Range<usize>,
+    /// 工作变量 g 的位分解
+    pub g_bits: Range<usize>,
+    /// 工作变量 h
+    pub h_col: usize,
+    /// 当前块的输入链接值 H（8个字），块内保持不变
+    pub hash_cols: Range<usize>,
+    /// 新 a 值模 2³² 加法的进位（3位）
+    pub a_carry_bits: Range<usize>,
+    /// 新 e 值模 2³² 加法的进位（3位）
+    pub e_carry_bits: Range<usize>,
+    /// 最后一轮加上链接值时 b, c, d, f, g, h 的进位（各1位）
+    pub final_carry_cols: Range<usize>,
+    /// 当前块是否为最后一块（块内保持不变）
+    pub last_block_col: usize,
+    /// 消息调度滑动窗口 W[t..t+16]（16个字）
+    pub w_cols: Range<usize>,
+    /// W[t+1] 的位分解（用于 σ₀）
+    pub w1_bits: Range<usize>,
+    /// W[t+14] 的位分解（用于 σ₁）
+    pub w14_bits: Range<usize>,
+    /// W[t+16] 模 2³² 加法的进位（2位）
+    pub w_carry_bits: Range<usize>,
+    /// 当前块的16个消息字 M[0..16]（块内保持不变）
+    pub msg_cols: Range<usize>,
+    /// 填充后消息的字节（第 r 行为第 r 个字节）
+    pub byte_col: usize,
+    /// 当前字已读入字节的大端累加值，在字的最后一个字节处等于消息字
+    pub word_acc_col: usize,
+    /// 本行字节是否属于原文（填充之前）
+    pub is_data_col: usize,
+    /// 本行字节是否为填充起始字节 `0x80`
+    pub pad_start_col: usize,
+    /// 截至本行的原文字节数
+    pub data_count_col: usize,
+    /// 子电路之后的第一个空闲列
+    pub end: usize,
+}
+
+impl Sha256Layout {
+    /// 从第 `start` 列开始依次分配子电路的列
+    pub fn new(start: usize) -> Self {
+        let mut next = start;
+        let mut alloc = |n: usize| {
+            let cols = next .. next + n;
+            next += n;
+            cols
+        };
+
+        Self {
+            round_cols: alloc(ROUNDS),
+            a_bits: alloc(WORD_BITS),
+            b_bits: alloc(WORD_BITS),
+            c_bits: alloc(WORD_BITS),
+            d_col: alloc(1).start,
+            e_bits: alloc(WORD_BITS),
+            f_bits: alloc(WORD_BITS),
+            g_bits: alloc(WORD_BITS),
+            h_col: alloc(1).start,
+            hash_cols: alloc(8),
+            a_carry_bits: alloc(3),
+            e_carry_bits: alloc(3),
+            final_carry_cols: alloc(6),
+            last_block_col: alloc(1).start,
+            w_cols: alloc(16),
+            w1_bits: alloc(WORD_BITS),
+            w14_bits: alloc(WORD_BITS),
+            w_carry_bits: alloc(2),
+            msg_cols: alloc(16),
+            byte_col: alloc(1).start,
+            word_acc_col: alloc(1).start,
+            is_data_col: alloc(1).start,
+            pad_start_col: alloc(1).start,
+            data_count_col: alloc(1).start,
+            end: alloc(0).start,
+        }
+    }
+
+    /// 状态部分的列范围（轮次选择子、工作变量、链接值、进位与末块标志）
+    pub fn state_cols(&self) -> Range<usize> {
+        self.round_cols.start .. self.last_block_col + 1
+    }
+
+    /// 消息部分的列范围（调度窗口及其位分解与进位、消息字、字节流与填充标志）
+    pub fn schedule_cols(&self) -> Range<usize> {
+        self.w_cols.start .. self.end
+    }
+
+    /// 全部布尔列
+    fn bit_cols(&self) -> impl Iterator<Item = usize> {
+        [
+            self.a_bits.clone(),
+            self.b_bits.clone(),
+            self.c_bits.clone(),
+            self.e_bits.clone(),
+            self.f_bits.clone(),
+            self.g_bits.clone(),
+            self.a_carry_bits.clone(),
+            self.e_carry_bits.clone(),
+            self.final_carry_cols.clone(),
+            self.last_block_col .. self.last_block_col + 1,
+            self.w1_bits.clone(),
+            self.w14_bits.clone(),
+            self.w_carry_bits.clone(),
+            self.is_data_col .. self.pad_start_col + 1,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// 消息需要的 trace 行数：每块64轮，外加一行摘要行
+    pub fn rows_for(&self, message_len: usize) -> usize {
+        sha256::num_blocks(message_len) * ROUNDS + 1
+    }
+
+    /// 填充 SHA-256 子电路的 trace 列
+    ///
+    /// 摘要行之后的行只保留链接值（即最终摘要），其余列为零
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        message: &[u8],
+    ) -> Result<(), ZkpError> {
+        let blocks = sha256::pad(message);
+        let height = trace[0].len();
+        if self.rows_for(message.len()) > height {
+            return Err(ZkpError::InvalidWitness(format!(
+                "SHA-256 trace needs {} rows, trace has {}",
+                self.rows_for(message.len()),
+                height
+            )));
+        }
+
+        let mut hv = IV;
+        let mut row = 0;
+        let mut word_acc = 0u32;
+        for (index, block) in blocks.iter().enumerate() {
+            // 窗口在第63轮覆盖 W[63..79]
+            let w = sha256::message_schedule(block, ROUNDS + 16);
+            let last_block = index == blocks.len() - 1;
+            let mut state = hv;
+
+            for t in 0 .. ROUNDS {
+                let [a, b, c, d, e, f, g, h] = state.map(u64::from);
+                let is_last = t == ROUNDS - 1;
+
+                trace[self.round_cols.start + t][row] = F::ONE;
+                trace[self.last_block_col][row] = F::from_bool(last_block);
+                self.write_state(trace, row, &state);
+                self.write_hash(trace, row, &hv);
+
+                // 消息字节流与填充标志
+                word_acc = if t % 4 == 0 { 0 } else { word_acc << 8 } | u32::from(block[t]);
+                for (col, &word) in self.msg_cols.clone().zip(&w[.. 16]) {
+                    trace[col][row] = F::from_u32(word);
+                }
+                trace[self.byte_col][row] = F::from_u8(block[t]);
+                trace[self.word_acc_col][row] = F::from_u32(word_acc);
+                trace[self.is_data_col][row] = F::from_bool(row < message.len());
+                trace[self.pad_start_col][row] = F::from_bool(row == message.len());
+                trace[self.data_count_col][row] = F::from_usize(message.len().min(row + 1));
+
+                for (col, &word) in self.w_cols.clone().zip(&w[t .. t + 16]) {
+                    trace[col][row] = F::from_u32(word);
+                }
+                write_bits(trace, &self.w1_bits, row, u64::from(w[t + 1]));
+                write_bits(trace, &self.w14_bits, row, u64::from(w[t + 14]));
+                if !is_last {
+                    let sum = u64::from(sha256::small_sigma1(w[t + 14]))
+                        + u64::from(w[t + 9])
+                        + u64::from(sha256::small_sigma0(w[t + 1]))
+                        + u64::from(w[t]);
+                    write_bits(trace, &self.w_carry_bits, row, sum >> 32);
+                }
+
+                let t1 = h
+                    + u64::from(sha256::big_sigma1(e as u32))
+                    + u64::from(sha256::ch(e as u32, f as u32, g as u32))
+                    + u64::from(K[t])
+                    + u64::from(w[t]);
+                let t2 = u64::from(sha256::big_sigma0(a as u32))
+                    + u64::from(sha256::maj(a as u32, b as u32, c as u32));
+
+                // 最后一轮直接加上链接值，得到下一块的输入链接值
+                let mut out = [t1 + t2, a, b, c, d + t1, e, f, g];
+                if is_last {
+                    for (x, &h) in out.iter_mut().zip(&hv) {
+                        *x += u64::from(h);
+                    }
+                }
+
+                write_bits(trace, &self.a_carry_bits, row, out[0] >> 32);
+                write_bits(trace, &self.e_carry_bits, row, out[4] >> 32);
+                for (col, i) in self.final_carry_cols.clone().zip([1, 2, 3, 5, 6, 7]) {
+                    trace[col][row] = F::from_bool(out[i] >> 32 == 1);
+                }
+
+                state = out.map(|x| x as u32);
+                row += 1;
+            }
+
+            hv = state;
+        }
+
+        // 摘要行：工作变量等于最终链接值
+        self.write_state(trace, row, &hv);
+        for row in row .. height {
+            self.write_hash(trace, row, &hv);
+            trace[self.data_count_col][row] = F::from_usize(message.len());
+        }
+
+        Ok(())
+    }
+
+    fn write_state<F: Field>(&self, trace: &mut [Vec<F>], row: usize, state: &[u32; 8]) {
+        let [a, b, c, d, e, f, g, h] = state.map(u64::from);
+        write_bits(trace, &self.a_bits, row, a);
+        write_bits(trace, &self.b_bits, row, b);
+        write_bits(trace, &self.c_bits, row, c);
+        trace[self.d_col][row] = F::from_u64(d);
+        write_bits(trace, &self.e_bits, row, e);
+        write_bits(trace, &self.f_bits, row, f);
+        write_bits(trace, &self.g_bits, row, g);
+        trace[self.h_col][row] = F::from_u64(h);
+    }
+
+    fn write_hash<F: Field>(&self, trace: &mut [Vec<F>], row: usize, hv: &[u32; 8]) {
+        for (col, &word) in self.hash_cols.clone().zip(hv) {
+            trace[col][row] = F::from_u32(word);
+        }
+    }
+
+    /// SHA-256 压缩函数约束
+    ///
+    /// `digest` 为公开承诺的8个大端字，约束最后一行的链接值等于它
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        digest: &[AB::Expr],
+    ) {
+        let two_32 = AB::Expr::from_u64(1 << 32);
+        let sel: Vec<AB::Expr> = self
+            .round_cols
+            .clone()
+            .map(|col| local[col].into())
+            .collect();
+        let is_round: AB::Expr = sel.iter().cloned().sum();
+        let last_round = sel[ROUNDS - 1].clone();
+
+        for col in self.bit_cols() {
+            builder.assert_bool(local[col]);
+        }
+
+        // 1. 轮次选择子：首行为第0轮，逐行右移一位，最后一行不处于任何轮次
+        builder.when_first_row().assert_one(sel[0].clone());
+        for s in &sel[1 ..] {
+            builder.when_first_row().assert_zero(s.clone());
+        }
+        // 非最后一块的第63轮之后重新开始第0轮
+        let last_block = local[self.last_block_col];
+        builder.when_transition().assert_eq(
+            next[self.round_cols.start],
+            last_round.clone() * (AB::Expr::ONE - last_block),
+        );
+        builder
+            .when(is_round.clone() - last_round.clone())
+            .assert_eq(next[self.last_block_col], last_block);
+        for (i, s) in sel[.. ROUNDS - 1].iter().enumerate() {
+            builder
+                .when_transition()
+                .assert_eq(next[self.round_cols.start + i + 1], s.clone());
+        }
+        builder.when_last_row().assert_zero(is_round.clone());
+
+        // 2. 初始状态：工作变量与链接值都等于 IV
+        let regs = self.registers::<AB>(local);
+        for (i, (reg, &iv)) in regs.iter().zip(&IV).enumerate() {
+            let iv = AB::Expr::from_u32(iv);
+            builder.when_first_row().assert_eq(reg.clone(), iv.clone());
+            builder
+                .when_first_row()
+                .assert_eq(local[self.hash_cols.start + i], iv);
+        }
+
+        // 3. 轮函数
+        // T₁ = h + Σ₁(e) + Ch(e,f,g) + K[t] + W[t]
+        // T₂ = Σ₀(a) + Maj(a,b,c)
+        // 最后一轮把链接值加到每个输出上，进位列吸收超出 2³² 的部分
+        let a = &local[self.a_bits.clone()];
+        let b = &local[self.b_bits.clone()];
+        let c = &local[self.c_bits.clone()];
+        let e = &local[self.e_bits.clone()];
+        let f = &local[self.f_bits.clone()];
+        let g = &local[self.g_bits.clone()];
+        let hash: Vec<AB::Expr> = self
+            .hash_cols
+            .clone()
+            .map(|col| local[col].into())
+            .collect();
+        let round_constant: AB::Expr = sel
+            .iter()
+            .zip(&K)
+            .map(|(s, &k)| s.clone() * AB::Expr::from_u32(k))
+            .sum();
+
+        let t1 = regs[7].clone()
+            + sigma::<AB>(e, [6, 11, 25], false)
+            + ch::<AB>(e, f, g)
+            + round_constant
+            + local[self.w_cols.start];
+        let t2 = sigma::<AB>(a, [2, 13, 22], false) + maj::<AB>(a, b, c);
+
+        let next_regs = self.registers::<AB>(next);
+        let final_carries: Vec<AB::Expr> = self
+            .final_carry_cols
+            .clone()
+            .map(|col| local[col].into())
+            .collect();
+        let carries = [
+            pack::<AB>(&local[self.a_carry_bits.clone()]),
+            final_carries[0].clone(),
+            final_carries[1].clone(),
+            final_carries[2].clone(),
+            pack::<AB>(&local[self.e_carry_bits.clone()]),
+            final_carries[3].clone(),
+            final_carries[4].clone(),
+            final_carries[5].clone(),
+        ];
+        let outputs = [
+            t1.clone() + t2,
+            regs[0].clone(),
+            regs[1].clone(),
+            regs[2].clone(),
+            regs[3].clone() + t1,
+            regs[4].clone(),
+            regs[5].clone(),
+            regs[6].clone(),
+        ];
+
+        for i in 0 .. 8 {
+            builder.when(is_round.clone()).assert_eq(
+                next_regs[i].clone() + carries[i].clone() * two_32.clone(),
+                outputs[i].clone() + last_round.clone() * hash[i].clone(),
+            );
+        }
+        for carry in final_carries {
+            builder
+                .when(AB::Expr::ONE - last_round.clone())
+                .assert_zero(carry);
+        }
+
+        // 4. 消息调度
+        // 窗口左移一个字，新字 W[t+16] = σ₁(W[t+14]) + W[t+9] + σ₀(W[t+1]) + W[t]
+        let w = |i: usize| local[self.w_cols.start + i];
+        let w1 = &local[self.w1_bits.clone()];
+        let w14 = &local[self.w14_bits.clone()];
+        builder
+            .when(is_round.clone())
+            .assert_eq(w(1), pack::<AB>(w1));
+        builder
+            .when(is_round.clone())
+            .assert_eq(w(14), pack::<AB>(w14));
+
+        let mut when_schedule = builder.when(is_round - last_round.clone());
+        for i in 0 .. 15 {
+            when_schedule.assert_eq(next[self.w_cols.start + i], w(i + 1));
+        }
+        when_schedule.assert_eq(
+            next[self.w_cols.start + 15] + pack::<AB>(&local[self.w_carry_bits.clone()]) * two_32,
+            sigma::<AB>(w14, [17, 19, 10], true) + w(9) + sigma::<AB>(w1, [7, 18, 3], true) + w(0),
+        );
+
+        // 5. 链接值：块结束时取该块的输出，其余行保持不变
+        for (i, reg) in next_regs.into_iter().enumerate() {
+            let col = self.hash_cols.start + i;
+            builder.when(last_round.clone()).assert_eq(next[col], reg);
+            builder
+                .when_transition()
+                .when(AB::Expr::ONE - last_round.clone())
+                .assert_eq(next[col], local[col]);
+        }
+
+        // 6. 最后一行的链接值即摘要，必须等于公开承诺
+        for (col, word) in self.hash_cols.clone().zip(digest) {
+            builder.when_last_row().assert_eq(local[col], word.clone());
+        }
+
+        // 7. 消息字与填充
+        self.eval_message(builder, local, next, &sel);
+    }
+
+    /// 消息字节流约束
+    ///
+    /// - 每块的16个消息字在块内不变，并作为调度窗口的初值
+    /// - 第 r 行的字节按大端累加，每4个字节等于一个消息字
+    /// - 原文字节之后紧跟 `0x80`，随后补零，最后一块的末8字节为原文位长
+    fn eval_message<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        sel: &[AB::Expr],
+    ) {
+        let is_round: AB::Expr = sel.iter().cloned().sum();
+        let last_round = sel[ROUNDS - 1].clone();
+        let next_sel: Vec<AB::Expr> = self
+            .round_cols
+            .clone()
+            .map(|col| next[col].into())
+            .collect();
+        let next_is_round: AB::Expr = next_sel.iter().cloned().sum();
+        let next_word_start: AB::Expr = next_sel.iter().step_by(4).cloned().sum();
+
+        let msg = |i: usize| local[self.msg_cols.start + i];
+        let byte = local[self.byte_col];
+        let word_acc = local[self.word_acc_col];
+        let is_data = local[self.is_data_col];
+        let pad_start = local[self.pad_start_col];
+        let data_count = local[self.data_count_col];
+        let last_block = local[self.last_block_col];
+
+        // 消息字块内不变，块首行的调度窗口即消息字
+        for i in 0 .. 16 {
+            builder
+                .when(sel[0].clone())
+                .assert_eq(local[self.w_cols.start + i], msg(i));
+            builder
+                .when(is_round.clone() - last_round.clone())
+                .assert_eq(next[self.msg_cols.start + i], msg(i));
+        }
+
+        // 字节按大端累加：字的首字节重新开始，最后一个字节处等于消息字
+        builder.when_first_row().assert_eq(word_acc, byte);
+        builder.when_transition().when(next_is_round).assert_eq(
+            next[self.word_acc_col],
+            (AB::Expr::ONE - next_word_start) * word_acc * AB::Expr::from_u16(256)
+                + next[self.byte_col],
+        );
+        let word_end_mismatch: AB::Expr = (0 .. 16)
+            .map(|j| sel[4 * j + 3].clone() * (word_acc.into() - msg(j)))
+            .sum();
+        builder.assert_zero(word_end_mismatch);
+
+        // 原文字节是轮次行的前缀，填充起始字节位于第一个非原文行
+        builder
+            .when_first_row()
+            .assert_eq(pad_start, AB::Expr::ONE - is_data);
+        builder.when_transition().assert_eq(
+            next[self.pad_start_col],
+            is_data.into() - next[self.is_data_col],
+        );
+        builder
+            .when(AB::Expr::ONE - is_round.clone())
+            .assert_zero(is_data);
+        builder
+            .when(AB::Expr::ONE - is_round.clone())
+            .assert_zero(pad_start);
+
+        // 最后一块的末8个字节是长度字段，原文与 0x80 都不能落在其中，其余非原文字节为零
+        let length_field = sel[56 ..].iter().cloned().sum::<AB::Expr>() * last_block;
+        builder.when(is_data).assert_zero(length_field.clone());
+        builder.when(pad_start).assert_zero(length_field.clone());
+        builder
+            .when(pad_start)
+            .assert_eq(byte, AB::Expr::from_u8(0x80));
+        builder
+            .when(is_round - is_data - pad_start - length_field)
+            .assert_zero(byte);
+
+        // 填充最短：0x80 落在最后一块，或落在倒数第二块的末8字节；不含原文的块必为最后一块
+        let tail: AB::Expr = sel[56 ..].iter().cloned().sum();
+        builder
+            .when(pad_start)
+            .when(AB::Expr::ONE - last_block)
+            .assert_one(tail);
+        builder
+            .when(sel[0].clone())
+            .when(AB::Expr::ONE - is_data)
+            .assert_one(last_block);
+
+        // 原文长度：最后一块的消息字 M[14] || M[15] 为原文位长（长度远小于 2³² 位）
+        builder.when_first_row().assert_eq(data_count, is_data);
+        builder.when_transition().assert_eq(
+            next[self.data_count_col],
+            data_count.into() + next[self.is_data_col],
+        );
+        let mut when_final = builder.when(last_round * last_block);
+        when_final.assert_zero(msg(14));
+        when_final.assert_eq(msg(15), data_count.into() * AB::Expr::from_u8(8));
+    }
+
+    /// 以字的形式读取工作变量 a..h
+    fn registers<AB: AirBuilder>(&self, row: &[AB::Var]) -> [AB::Expr; 8] {
+        [
+            pack::<AB>(&row[self.a_bits.clone()]),
+            pack::<AB>(&row[self.b_bits.clone()]),
+            pack::<AB>(&row[self.c_bits.clone()]),
+            row[self.d_col].into(),
+            pack::<AB>(&row[self.e_bits.clone()]),
+            pack::<AB>(&row[self.f_bits.clone()]),
+            pack::<AB>(&row[self.g_bits.clone()]),
+            row[self.h_col].into(),
+        ]
+    }
+}
+
+/// 把逐位表达式（低位在前）打包成字
+fn pack_exprs<AB: AirBuilder>(bits: impl DoubleEndedIterator<Item = AB::Expr>) -> AB::Expr {
+    bits.rev()
+        .fold(AB::Expr::ZERO, |acc, bit| acc.double() + bit)
+}
+
+/// 布尔值异或：x ⊕ y = x + y - 2xy
+fn xor<AB: AirBuilder>(x: AB::Expr, y: AB::Expr) -> AB::Expr {
+    x.clone() + y.clone() - (x * y).double()
+}
+
+/// Σ/σ 函数：`ROTR^r0 ⊕ ROTR^r1 ⊕ (ROTR|SHR)^r2`
+///
+/// `shift_last` 为真时第三项是逻辑右移（σ₀、σ₁），高位补零
+fn sigma<AB: AirBuilder>(bits: &[AB::Var], [r0, r1, r2]: [usize; 3], shift_last: bool) -> AB::Expr {
+    pack_exprs::<AB>((0 .. WORD_BITS).map(|i| {
+        let x = xor::<AB>(
+            bits[(i + r0) % WORD_BITS].into(),
+            bits[(i + r1) % WORD_BITS].into(),
+        );
+        if shift_last && i + r2 >= WORD_BITS {
+            x
+        } else {
+            xor::<AB>(x, bits[(i + r2) % WORD_BITS].into())
+        }
+    }))
+}
+
+/// Ch(e, f, g) 逐位为 e·f + (1 - e)·g = e·(f - g) + g
+fn ch<AB: AirBuilder>(e: &[AB::Var], f: &[AB::Var], g: &[AB::Var]) -> AB::Expr {
+    pack_exprs::<AB>((0 .. WORD_BITS).map(|i| {
+        let (f, g): (AB::Expr, AB::Expr) = (f[i].into(), g[i].into());
+        (f - g.clone()) * e[i] + g
+    }))
+}
+
+/// Maj(a, b, c) 逐位为 ab + ac + bc - 2abc
+fn maj<AB: AirBuilder>(a: &[AB::Var], b: &[AB::Var], c: &[AB::Var]) -> AB::Expr {
+    pack_exprs::<AB>((0 .. WORD_BITS).map(|i| {
+        let (a, b, c): (AB::Expr, AB::Expr, AB::Expr) = (a[i].into(), b[i].into(), c[i].into());
+        let ab = a.clone() * b.clone();
+        ab.clone() + a * c.clone() + b * c.clone() - (ab * c).double()
+    }))
+}
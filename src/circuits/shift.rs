@@ -0,0 +1,162 @@
+use std::ops::Range;
+
+use p3_air::AirBuilder;
+use p3_field::{Field, PrimeCharacteristicRing};
+
+use crate::circuits::trace::TraceLayout;
+
+/// 填充与未使用行上移位列的取值，不等于任何字节
+pub const OUTSIDE_DATA: u32 = 256;
+
+/// 第 `row` 行的原文字节，原文之外为 [`OUTSIDE_DATA`]
+pub fn byte_at(plaintext: &[u8], row: usize) -> u32 {
+    plaintext
+        .get(row)
+        .map_or(OUTSIDE_DATA, |&byte| u32::from(byte))
+}
+
+/// 移位列
+///
+/// `shift_j` 在第 k 行保存第 k+j 行的原文字节，原文之外为 256，
+/// 因此第 k 行能同时看到从 k 开始的 `cols.len()` 个字节
+#[derive(Debug, Clone)]
+pub struct ShiftColumns {
+    /// 移位列 `shift_0..shift_n`
+    pub cols: Range<usize>,
+}
+
+impl ShiftColumns {
+    /// 使用给定的列作为移位列
+    pub fn new(cols: Range<usize>) -> Self {
+        Self { cols }
+    }
+
+    /// 填充移位列
+    pub fn generate_trace<F: Field>(&self, trace: &mut [Vec<F>], plaintext: &[u8]) {
+        for (j, col) in self.cols.clone().enumerate() {
+            for (row, cell) in trace[col].iter_mut().enumerate() {
+                *cell = F::from_u32(byte_at(plaintext, row + j));
+            }
+        }
+    }
+
+    /// 移位约束
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+    ) {
+        let shift = |row: &[AB::Var], j: usize| row[self.cols.start + j];
+
+        // shift_0 = plaintext + 256·(1 - is_data)：原文字节或 256
+        let is_data = local[layout.sha256.is_data_col];
+        builder.assert_eq(
+            shift(local, 0),
+            local[layout.plaintext_col]
+                + (AB::Expr::ONE - is_data) * AB::Expr::from_u32(OUTSIDE_DATA),
+        );
+
+        // shift_j = 下一行的 shift_{j-1}
+        for j in 1 .. self.cols.len() {
+            builder
+                .when_transition()
+                .assert_eq(shift(local, j), shift(next, j - 1));
+        }
+    }
+}
+
+/// 失配见证子电路的列布局
+///
+/// 在移位列之上，一行（一个候选起点）可以给出失配见证：选择一个下标 `j < |t|`
+/// 并给出 `shift_j - t[j]` 的逆元，证明从该行开始的窗口不等于 `t`。
+/// 越过原文末尾的窗口总能在第一个原文之外的字节处失配。
+/// 不出现子电路要求每一行都失配，出现计数子电路只要求未出现的行失配
+#[derive(Debug, Clone)]
+pub struct MismatchLayout {
+    /// 移位列（`max_substring_len` 列）
+    pub shift: ShiftColumns,
+    /// 失配下标选择子（`max_substring_len` 列，失配时 one-hot）
+    pub sel_cols: Range<usize>,
+    /// 失配差值的逆元
+    pub inverse_col: usize,
+    /// 子电路之后的第一列
+    pub end: usize,
+}
+
+impl MismatchLayout {
+    /// 从第 `start` 列开始依次分配子电路的列
+    pub fn new(start: usize, max_substring_len: usize) -> Self {
+        let shift = start .. start + max_substring_len;
+        let sel_cols = shift.end .. shift.end + max_substring_len;
+
+        Self {
+            shift: ShiftColumns::new(shift),
+            inverse_col: sel_cols.end,
+            end: sel_cols.end + 1,
+            sel_cols,
+        }
+    }
+
+    /// 填充子电路的 trace 列：每个从该行开始的窗口不等于 `value` 的行取第一个失配下标。
+    /// 返回每一行是否为 `value` 的一次出现
+    pub fn generate_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        plaintext: &[u8],
+        value: &[u8],
+    ) -> Vec<bool> {
+        self.shift.generate_trace(trace, plaintext);
+
+        let height = trace[0].len();
+        (0 .. height)
+            .map(|row| {
+                let mismatch = (0 .. value.len())
+                    .find(|&j| byte_at(plaintext, row + j) != u32::from(value[j]));
+                if let Some(j) = mismatch {
+                    let diff = F::from_u32(byte_at(plaintext, row + j)) - F::from_u8(value[j]);
+                    trace[self.sel_cols.start + j][row] = F::ONE;
+                    trace[self.inverse_col][row] = diff.inverse();
+                }
+                mismatch.is_none()
+            })
+            .collect()
+    }
+
+    /// 失配约束
+    ///
+    /// `string_pv` 为公开值中该字符串的 `[|t|, t[0..max], mask[0..max]]`；
+    /// `mismatched` 为1的行上失配下标 one-hot、落在 t 的长度之内且选中位置的差值非零，
+    /// 为0的行上不选任何下标
+    pub fn eval<AB: AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &[AB::Var],
+        next: &[AB::Var],
+        layout: &TraceLayout,
+        string_pv: &[AB::Expr],
+        mismatched: AB::Expr,
+    ) {
+        let max_substring_len = self.sel_cols.len();
+        let bytes = &string_pv[1 .. 1 + max_substring_len];
+        let mask = &string_pv[1 + max_substring_len .. 1 + 2 * max_substring_len];
+        let shift = |j: usize| local[self.shift.cols.start + j];
+        let sel = |j: usize| local[self.sel_cols.start + j];
+
+        self.shift.eval(builder, local, next, layout);
+
+        let mut selected = AB::Expr::ZERO;
+        let mut diff = AB::Expr::ZERO;
+        for j in 0 .. max_substring_len {
+            builder.assert_bool(sel(j));
+            builder.when(sel(j)).assert_one(mask[j].clone());
+            selected += sel(j).into();
+            diff += sel(j) * (shift(j) - bytes[j].clone());
+        }
+        builder.assert_eq(selected, mismatched.clone());
+
+        // 选中位置的差值非零：diff · inverse = 1
+        builder.assert_eq(diff * local[self.inverse_col], mismatched);
+    }
+}
@@ -1,7 +1,15 @@
 use p3_field::{Field, integers::QuotientMap};
 use sha2::{Digest, Sha256};
 
-use super::Circuit;
+use super::{
+    Circuit,
+    air::SubstringAIR,
+    normalize::normalized_occurrences,
+    number::read_number,
+    occurrence::{masked_occurrences, occurrences},
+    redaction,
+    trace::{TraceGenerator, TraceLayout},
+};
 use crate::{
     config::{CircuitConfig, CircuitParams},
     error::ZkpError,
@@ -9,7 +17,8 @@ use crate::{
 
 /// 子串包含证明电路
 ///
-/// 电路目标：证明公开子串 `s` 包含在某个原文中，且原文的 SHA-256 哈希等于公开承诺
+/// 电路目标：证明公开子串 `s` 包含在某个原文中，且原文的 SHA-256 哈希等于公开承诺；
+/// 多个窗口（如锚文本与目标子串）共享同一份原文与同一次哈希计算
 ///
 /// 约束系统：
 /// 1. 哈希一致性：SHA256(plaintext) == commitment
@@ -42,19 +51,246 @@ impl SubstringCircuit {
             )));
         }
 
-        // 检查子串长度限制
-        if params.public_inputs.substring.len() > self.config.max_substring_len {
+        let windows = &params.public_inputs.windows;
+        if witness.offsets.len() != windows.len() {
             return Err(ZkpError::InvalidWitness(format!(
-                "Substring too long: {} > {}",
-                params.public_inputs.substring.len(),
-                self.config.max_substring_len
+                "Expected {} offsets, got {}",
+                windows.len(),
+                witness.offsets.len()
+            )));
+        }
+
+        for (window, &offset) in windows.iter().zip(&witness.offsets) {
+            // 检查子串长度限制
+            if window.bytes.len() > self.config.max_substring_len {
+                return Err(ZkpError::InvalidWitness(format!(
+                    "Substring too long: {} > {}",
+                    window.bytes.len(),
+                    self.config.max_substring_len
+                )));
+            }
+
+            // 检查偏移合法性（偏移可由调用方指定，需防止溢出）
+            if offset
+                .checked_add(window.bytes.len())
+                .is_none_or(|end| end > witness.plaintext.len())
+            {
+                return Err(ZkpError::InvalidWitness(
+                    "Substring offset out of bounds".to_string(),
+                ));
+            }
+        }
+
+        // 检查公开长度
+        if let Some(length) = params.public_inputs.length
+            && length != witness.plaintext.len()
+        {
+            return Err(ZkpError::InvalidWitness(format!(
+                "Plaintext length {} does not match public length {}",
+                witness.plaintext.len(),
+                length
             )));
         }
 
-        // 检查偏移合法性
-        if witness.offset + params.public_inputs.substring.len() > witness.plaintext.len() {
+        // 检查标签之后的数值落在公开区间内
+        for range in &params.public_inputs.ranges {
+            let label = windows
+                .get(range.window)
+                .ok_or(ZkpError::InvalidPublicInput(
+                    "Range label window out of bounds".to_string(),
+                ))?;
+            let start = witness.offsets[range.window] + label.bytes.len();
+            let (value, _) = read_number(&witness.plaintext, start).ok_or(
+                ZkpError::InvalidWitness("No decimal number after the label".to_string()),
+            )?;
+            if value < range.min || value > range.max {
+                return Err(ZkpError::InvalidWitness(format!(
+                    "Value is outside [{}, {}]",
+                    range.min, range.max
+                )));
+            }
+        }
+
+        // 检查窗口的先后顺序
+        for order in &params.public_inputs.orders {
+            let (Some(before), Some(_)) = (windows.get(order.before), windows.get(order.after))
+            else {
+                return Err(ZkpError::InvalidPublicInput(
+                    "Ordered window out of bounds".to_string(),
+                ));
+            };
+            if witness.offsets[order.before] + before.bytes.len() > witness.offsets[order.after] {
+                return Err(ZkpError::InvalidWitness(format!(
+                    "Window {} does not end before window {} starts",
+                    order.before, order.after
+                )));
+            }
+        }
+
+        // 检查公开起点：窗口必须恰好从公开偏移开始
+        for position in &params.public_inputs.positions {
+            let window = windows
+                .get(position.window)
+                .ok_or(ZkpError::InvalidPublicInput(
+                    "Positioned window out of bounds".to_string(),
+                ))?;
+            let end = position.offset.checked_add(window.bytes.len()).ok_or(
+                ZkpError::InvalidPublicInput("Public offset out of bounds".to_string()),
+            )?;
+            if witness.offsets[position.window] != position.offset
+                || witness.plaintext.get(position.offset .. end) != Some(&window.bytes[..])
+            {
+                return Err(ZkpError::InvalidWitness(format!(
+                    "Substring does not occur at public offset {}",
+                    position.offset
+                )));
+            }
+        }
+
+        // 检查前缀与后缀窗口的位置
+        for &w in &params.public_inputs.prefixes {
+            let window = windows.get(w).ok_or(ZkpError::InvalidPublicInput(
+                "Prefix window out of bounds".to_string(),
+            ))?;
+            if witness.offsets[w] != 0 || !witness.plaintext.starts_with(&window.bytes) {
+                return Err(ZkpError::InvalidWitness(
+                    "Prefix window does not start the plaintext".to_string(),
+                ));
+            }
+        }
+        for &w in &params.public_inputs.suffixes {
+            let window = windows.get(w).ok_or(ZkpError::InvalidPublicInput(
+                "Suffix window out of bounds".to_string(),
+            ))?;
+            if witness.offsets[w] + window.bytes.len() != witness.plaintext.len()
+                || !witness.plaintext.ends_with(&window.bytes)
+            {
+                return Err(ZkpError::InvalidWitness(
+                    "Suffix window does not end the plaintext".to_string(),
+                ));
+            }
+        }
+
+        // 检查第 n 次出现：窗口起点之前（含起点）恰有 n 次出现
+        for nth in &params.public_inputs.nth_occurrences {
+            let window = windows.get(nth.window).ok_or(ZkpError::InvalidPublicInput(
+                "Occurrence window out of bounds".to_string(),
+            ))?;
+            let offset = witness.offsets[nth.window];
+            let mut starts = occurrences(&witness.plaintext, &window.bytes);
+            if nth.n == 0 || starts.nth(nth.n - 1) != Some(offset) {
+                return Err(ZkpError::InvalidWitness(format!(
+                    "Window is not occurrence {} of its substring",
+                    nth.n
+                )));
+            }
+        }
+
+        // 检查规范化窗口：窗口起点是规范化子串在规范化原文中的一次出现
+        for normalized in &params.public_inputs.normalized {
+            let window = windows
+                .get(normalized.window)
+                .ok_or(ZkpError::InvalidPublicInput(
+                    "Normalized window out of bounds".to_string(),
+                ))?;
+            let rules = normalized.normalization.rules();
+            let starts = normalized_occurrences(&witness.plaintext, &window.bytes, &rules);
+            if !starts.contains(&witness.offsets[normalized.window]) {
+                return Err(ZkpError::InvalidWitness(
+                    "Normalized substring does not occur at the window offset".to_string(),
+                ));
+            }
+        }
+
+        // 检查掩码窗口：未隐藏的字节与原文相等
+        for masked in &params.public_inputs.masked {
+            let window = windows
+                .get(masked.window)
+                .ok_or(ZkpError::InvalidPublicInput(
+                    "Masked window out of bounds".to_string(),
+                ))?;
+            if masked.hidden.len() != window.bytes.len() {
+                return Err(ZkpError::InvalidPublicInput(
+                    "Masked window needs one hidden flag per byte".to_string(),
+                ));
+            }
+            let offset = witness.offsets[masked.window];
+            if !masked_occurrences(&witness.plaintext, &window.bytes, &masked.hidden)
+                .any(|start| start == offset)
+            {
+                return Err(ZkpError::InvalidWitness(
+                    "Masked substring does not occur at the window offset".to_string(),
+                ));
+            }
+        }
+
+        // 检查涂黑文档：覆盖整份原文（长度公开），未涂黑的字节与原文同一位置的字节相等
+        for document in &params.public_inputs.redacted {
+            if document.bytes.is_empty() || document.hidden.len() != document.bytes.len() {
+                return Err(ZkpError::InvalidPublicInput(
+                    "Redacted document needs at least one byte and one hidden flag per byte"
+                        .to_string(),
+                ));
+            }
+            if params.public_inputs.length != Some(document.bytes.len()) {
+                return Err(ZkpError::InvalidPublicInput(
+                    "Redacted document length must be the public plaintext length".to_string(),
+                ));
+            }
+            if !redaction::derives(&witness.plaintext, document) {
+                return Err(ZkpError::InvalidWitness(
+                    "Redacted document does not match the plaintext".to_string(),
+                ));
+            }
+        }
+
+        // 检查不出现的字节串
+        for absent in &params.public_inputs.absent {
+            if absent.bytes.len() > self.config.max_substring_len {
+                return Err(ZkpError::InvalidWitness(format!(
+                    "Absent string too long: {} > {}",
+                    absent.bytes.len(),
+                    self.config.max_substring_len
+                )));
+            }
+            if absent.bytes.is_empty()
+                || occurrences(&witness.plaintext, &absent.bytes)
+                    .next()
+                    .is_some()
+            {
+                return Err(ZkpError::InvalidWitness(
+                    "Plaintext contains the absent string".to_string(),
+                ));
+            }
+        }
+
+        // 检查被计数字节串的出现次数
+        for count in &params.public_inputs.counts {
+            if count.bytes.is_empty() || count.bytes.len() > self.config.max_substring_len {
+                return Err(ZkpError::InvalidPublicInput(format!(
+                    "Counted string length {} outside [1, {}]",
+                    count.bytes.len(),
+                    self.config.max_substring_len
+                )));
+            }
+            let occurs = occurrences(&witness.plaintext, &count.bytes).count();
+            if occurs < count.min || occurs > count.max {
+                return Err(ZkpError::InvalidWitness(format!(
+                    "Substring occurs {} times, outside [{}, {}]",
+                    occurs, count.min, count.max
+                )));
+            }
+        }
+
+        // 检查原文匹配公开模式
+        if !params
+            .public_inputs
+            .dfas
+            .iter()
+            .all(|dfa| dfa.is_match(&witness.plaintext))
+        {
             return Err(ZkpError::InvalidWitness(
-                "Substring offset out of bounds".to_string(),
+                "Plaintext does not match the pattern".to_string(),
             ));
         }
 
@@ -82,68 +318,52 @@ impl SubstringCircuit {
             .as_ref()
             .ok_or(ZkpError::InvalidWitness("Missing witness".to_string()))?;
 
-        let substring = &params.public_inputs.substring;
-        let start = witness.offset;
-        let end = start + substring.len();
-
-        if end > witness.plaintext.len() {
-            return Ok(false);
-        }
+        // 规范化窗口与规范化后的原文比较，掩码窗口跳过被隐藏的字节
+        let public_inputs = &params.public_inputs;
+        Ok(public_inputs
+            .windows
+            .iter()
+            .zip(&witness.offsets)
+            .enumerate()
+            .all(|(w, (window, &start))| {
+                let normalized = public_inputs
+                    .normalized
+                    .iter()
+                    .find(|normalized| normalized.window == w);
+                let masked = public_inputs
+                    .masked
+                    .iter()
+                    .find(|masked| masked.window == w);
+                match (normalized, masked) {
+                    (Some(normalized), _) => {
+                        let rules = normalized.normalization.rules();
+                        normalized_occurrences(&witness.plaintext, &window.bytes, &rules)
+                            .contains(&start)
+                    }
+                    (None, Some(masked)) => {
+                        masked_occurrences(&witness.plaintext, &window.bytes, &masked.hidden)
+                            .any(|offset| offset == start)
+                    }
+                    (None, None) => {
+                        witness.plaintext.get(start .. start + window.bytes.len())
+                            == Some(window.bytes.as_slice())
+                    }
+                }
+            }))
+    }
 
-        let plaintext_substring = &witness.plaintext[start .. end];
-        Ok(plaintext_substring == substring)
+    /// 按公开输入分配列布局
+    fn layout(&self, params: &CircuitParams) -> TraceLayout {
+        TraceLayout::for_inputs(&self.config, &params.public_inputs)
     }
 }
 
-impl<F: Field + QuotientMap<u8>> Circuit<F> for SubstringCircuit {
+impl<F: Field + QuotientMap<u8> + QuotientMap<usize>> Circuit<F> for SubstringCircuit {
     fn generate_trace(&self, params: &CircuitParams) -> Result<Vec<Vec<F>>, ZkpError> {
         self.validate_params(params)?;
 
-        let witness = params.witness.as_ref().unwrap();
-        let public_inputs = &params.public_inputs;
-
-        // Trace 布局：
-        // - 列0-31: SHA-256 状态寄存器 (a, b, c, d, e, f, g, h)，每个4字节
-        // - 列32-95: SHA-256 消息调度 (W[0]...W[63])，每个4字节
-        // - 列96: 原文字节值
-        // - 列97: 子串字节值
-        // - 列98: 字节匹配标志 (0/1)
-        // - 列99: 偏移位置指示器
-        // - 列100: 范围检查列
-
-        let trace_len = self.config.max_text_len.max(64); // SHA-256 需要至少64步
-        let num_columns = 101;
-        let mut trace = vec![vec![F::ZERO; trace_len]; num_columns];
-
-        // TODO: 实现完整的 SHA-256 trace 生成
-        // 这里先做简化实现，实际应该使用 p3-sha256 的逻辑
-
-        // 填充原文字节
-        for (i, &byte) in witness.plaintext.iter().enumerate() {
-            trace[96][i] = F::from_int(byte);
-        }
-
-        // 填充子串匹配窗口
-        let offset = witness.offset;
-        for (i, &byte) in public_inputs.substring.iter().enumerate() {
-            let pos = offset + i;
-            if pos < trace_len {
-                trace[97][pos] = F::from_int(byte);
-                // 设置匹配标志
-                if pos < witness.plaintext.len() && witness.plaintext[pos] == byte {
-                    trace[98][pos] = F::ONE;
-                }
-            }
-        }
-
-        // 设置偏移位置指示器
-        for i in offset .. offset + public_inputs.substring.len() {
-            if i < trace_len {
-                trace[99][i] = F::ONE;
-            }
-        }
-
-        Ok(trace)
+        // Trace 布局见 `TraceLayout`，行数只取决于电路配置
+        TraceGenerator::with_layout(self.layout(params), self.config.clone()).generate_trace(params)
     }
 
     fn verify_constraints(
@@ -161,23 +381,9 @@ impl<F: Field + QuotientMap<u8>> Circuit<F> for SubstringCircuit {
             return Ok(false);
         }
 
-        // 验证 trace 中的约束
-        let _substring_len = params.public_inputs.substring.len();
-
-        // 检查匹配标志约束：在匹配窗口内，所有位置都应该匹配
-        for i in 0 .. trace[0].len() {
-            if trace[99][i] == F::ONE {
-                // 在匹配窗口内
-                if trace[98][i] != F::ONE {
-                    // 但没有匹配标志
-                    return Ok(false);
-                }
-                // 检查字节值是否匹配
-                if trace[96][i] != trace[97][i] {
-                    return Ok(false);
-                }
-            }
-        }
+        // 使用与证明系统相同的 AIR 验证 trace 中的约束
+        let air = SubstringAIR::new(self.config.clone(), self.layout(params));
+        air.verify_all_constraints(trace, &params.public_inputs)?;
 
         Ok(true)
     }
@@ -193,7 +399,7 @@ mod tests {
     use sha2::{Digest, Sha256};
 
     use super::*;
-    use crate::config::{CircuitWitness, PublicInputs};
+    use crate::config::{CircuitWitness, PublicInputs, WindowPosition};
 
     #[test]
     fn test_hello_world_example() {
@@ -211,13 +417,10 @@ mod tests {
 
         let params = CircuitParams {
             config,
-            public_inputs: PublicInputs {
-                commitment,
-                substring,
-            },
+            public_inputs: PublicInputs::substring(commitment, substring),
             witness: Some(CircuitWitness {
                 plaintext,
-                offset: 0,
+                offsets: vec![0],
             }),
         };
 
@@ -244,13 +447,10 @@ mod tests {
 
         let params = CircuitParams {
             config,
-            public_inputs: PublicInputs {
-                commitment,
-                substring,
-            },
+            public_inputs: PublicInputs::substring(commitment, substring),
             witness: Some(CircuitWitness {
                 plaintext,
-                offset: 0,
+                offsets: vec![0],
             }),
         };
 
@@ -259,4 +459,32 @@ mod tests {
         let is_valid = circuit_typed.verify_constraints(&trace, &params).unwrap();
         assert!(!is_valid);
     }
+
+    #[test]
+    fn test_offset_overflow_rejected() {
+        let circuit = SubstringCircuit::new(CircuitConfig::default());
+        let plaintext = b"hello world!".to_vec();
+        let public_inputs = PublicInputs {
+            positions: vec![WindowPosition {
+                window: 0,
+                offset: usize::MAX,
+            }],
+            ..PublicInputs::substring(Sha256::digest(&plaintext).into(), b"hello".to_vec())
+        };
+        let params = CircuitParams {
+            config: CircuitConfig::default(),
+            public_inputs,
+            witness: Some(CircuitWitness {
+                plaintext,
+                offsets: vec![0],
+            }),
+        };
+
+        // 偏移加子串长度溢出时返回错误而不是 panic
+        let circuit_typed: &dyn Circuit<Goldilocks> = &circuit;
+        assert!(matches!(
+            circuit_typed.generate_trace(&params),
+            Err(ZkpError::InvalidPublicInput(_))
+        ));
+    }
 }
@@ -1,43 +1,209 @@
 use p3_field::{Field, integers::QuotientMap};
-use crate::config::{CircuitConfig, CircuitParams};
+use p3_matrix::dense::RowMajorMatrix;
+use crate::circuits::bits::write_bits;
+use crate::circuits::absence::AbsenceLayout;
+use crate::circuits::normalize::{NormalizerLayout, normalize};
+use crate::circuits::number::NumberLayout;
+use crate::circuits::redaction::{self, RedactionLayout};
+use crate::circuits::occurrence::{CountLayout, NthOccurrenceLayout, OccurrenceLayout};
+use crate::circuits::regex::RegexLayout;
+use crate::circuits::sha256::Sha256Layout;
+use crate::config::{CircuitConfig, CircuitParams, PublicInputs, WindowOrder};
 use crate::error::ZkpError;
+use crate::hash::sha256::{self, ROUNDS};
 
 /// 计算轨迹（Execution Trace）的列定义
 /// 
 /// Trace 矩阵布局：每行代表一个时间步，每列代表一个电路变量
 #[derive(Debug, Clone)]
 pub struct TraceLayout {
-    /// SHA-256 状态寄存器列（8个32位字 = 32列）
+    /// SHA-256 压缩函数子电路的列
+    pub sha256: Sha256Layout,
+    /// SHA-256 状态列（轮次选择子、工作变量、链接值与进位）
     pub sha_state_cols: std::ops::Range<usize>,
-    /// SHA-256 消息调度列（64个32位字 = 64列） 
+    /// SHA-256 消息调度列（16字滑动窗口及其位分解与进位）
     pub sha_schedule_cols: std::ops::Range<usize>,
     /// 原文字节值列
     pub plaintext_col: usize,
+    /// 消息字节的8位分解列（确保字节值在[0,255]）
+    pub range_bits_cols: std::ops::Range<usize>,
+    /// 匹配窗口，各自对应一段公开字节串与一个私有偏移
+    pub windows: Vec<WindowLayout>,
+    /// 子串不出现子电路，每个不出现的字节串一组
+    pub absent: Vec<AbsenceLayout>,
+    /// 十进制数解析与区间比较子电路，每个数值区间一组
+    pub numbers: Vec<NumberLayout>,
+    /// 正则匹配子电路，每个模式一组
+    pub regexes: Vec<RegexLayout>,
+    /// 窗口之间的先后顺序（只有约束，不占用列）
+    pub orders: Vec<WindowOrder>,
+    /// 起点公开的窗口下标（起点本身在公开值中，不占用列）
+    pub positions: Vec<usize>,
+    /// 第 n 次出现子电路，每个命题一组
+    pub nth_occurrences: Vec<NthOccurrenceLayout>,
+    /// 出现次数子电路，每个被计数的字节串一组
+    pub counts: Vec<CountLayout>,
+    /// 固定在原文开头的窗口下标（只有约束，不占用列）
+    pub prefixes: Vec<usize>,
+    /// 固定在原文结尾的窗口下标（只有约束，不占用列）
+    pub suffixes: Vec<usize>,
+    /// 规范化子电路，每个与规范化原文比较的窗口一组
+    pub normalizers: Vec<NormalizerLayout>,
+    /// 部分字节被隐藏的窗口下标（隐藏标志在公开值中，不占用列）
+    pub masked: Vec<usize>,
+    /// 涂黑文档子电路，每份涂黑文档一组
+    pub redactions: Vec<RedactionLayout>,
+    /// 总列数
+    pub total_columns: usize,
+}
+
+/// 一个匹配窗口的列
+#[derive(Debug, Clone)]
+pub struct WindowLayout {
     /// 子串字节值列
     pub substring_col: usize,
     /// 字节匹配标志列（0/1）
     pub match_flag_col: usize,
     /// 偏移位置指示器列（标记匹配窗口）
     pub offset_indicator_col: usize,
-    /// 范围检查列（确保字节值在[0,255]）
-    pub range_check_col: usize,
-    /// 总列数
-    pub total_columns: usize,
+    /// 窗口计数列（偏移指示器的前缀和）
+    pub window_count_col: usize,
+    /// 子串下标选择子（`max_substring_len` 列 one-hot，第 j 列为1表示本行是窗口的第 j 个字节）
+    pub substring_sel_cols: std::ops::Range<usize>,
 }
 
-impl Default for TraceLayout {
-    fn default() -> Self {
+impl WindowLayout {
+    /// 从 `start` 开始分配一个窗口的列
+    fn new(start: usize, max_substring_len: usize) -> Self {
         Self {
-            sha_state_cols: 0..32,        // 列 0-31: SHA-256 状态 (8个寄存器 * 4字节)
-            sha_schedule_cols: 32..96,    // 列 32-95: 消息调度 (64个字 * 1列)
-            plaintext_col: 96,            // 列 96: 原文字节
-            substring_col: 97,            // 列 97: 子串字节
-            match_flag_col: 98,           // 列 98: 匹配标志
-            offset_indicator_col: 99,     // 列 99: 偏移指示器
-            range_check_col: 100,         // 列 100: 范围检查
-            total_columns: 101,
+            substring_col: start,            // 子串字节
+            match_flag_col: start + 1,       // 匹配标志
+            offset_indicator_col: start + 2, // 偏移指示器
+            window_count_col: start + 3,     // 窗口计数
+            substring_sel_cols: start + 4 .. start + 4 + max_substring_len, // 子串下标选择子
         }
     }
+
+    /// 窗口占用的列数
+    fn width(max_substring_len: usize) -> usize {
+        4 + max_substring_len
+    }
+}
+
+impl TraceLayout {
+    /// 按电路配置分配单个子串窗口的列
+    pub fn new(config: &CircuitConfig) -> Self {
+        Self::with_windows(config, 1)
+    }
+
+    /// 按电路配置分配 `num_windows` 个匹配窗口的列
+    ///
+    /// 每个窗口的子串选择子列数取决于 `max_substring_len`
+    pub fn with_windows(config: &CircuitConfig, num_windows: usize) -> Self {
+        // SHA-256 子电路占据前面的列，字节处理列紧随其后
+        let sha256 = Sha256Layout::new(0);
+        let byte_cols = sha256.end;
+        let windows_start = byte_cols + 9;
+        let window_width = WindowLayout::width(config.max_substring_len);
+        let windows: Vec<WindowLayout> = (0 .. num_windows)
+            .map(|w| WindowLayout::new(windows_start + w * window_width, config.max_substring_len))
+            .collect();
+
+        Self {
+            sha_state_cols: sha256.state_cols(),
+            sha_schedule_cols: sha256.schedule_cols(),
+            sha256,
+            plaintext_col: byte_cols,                        // 原文字节
+            range_bits_cols: byte_cols + 1 .. byte_cols + 9, // 字节位分解
+            windows,
+            absent: Vec::new(),
+            numbers: Vec::new(),
+            regexes: Vec::new(),
+            orders: Vec::new(),
+            positions: Vec::new(),
+            nth_occurrences: Vec::new(),
+            counts: Vec::new(),
+            prefixes: Vec::new(),
+            suffixes: Vec::new(),
+            normalizers: Vec::new(),
+            masked: Vec::new(),
+            redactions: Vec::new(),
+            total_columns: windows_start + num_windows * window_width,
+        }
+    }
+
+    /// 按公开输入分配列布局：每个公开字节串一个窗口，每个不出现的字节串一组移位与失配列，
+    /// 每个数值区间一组数值子电路，每个模式一组按 DFA 大小分配的匹配子电路，
+    /// 每个第 n 次出现命题与每个出现次数命题各一组计数子电路，每个规范化窗口一组映射子电路，
+    /// 每份涂黑文档一组按翻转边界数分配的掩码与指纹子电路
+    pub fn for_inputs(config: &CircuitConfig, public_inputs: &PublicInputs) -> Self {
+        let mut layout = Self::with_windows(config, public_inputs.windows.len());
+        for _ in &public_inputs.absent {
+            let absence = AbsenceLayout::new(layout.total_columns, config.max_substring_len);
+            layout.total_columns = absence.end;
+            layout.absent.push(absence);
+        }
+        for range in &public_inputs.ranges {
+            let number = NumberLayout::new(layout.total_columns, range.window);
+            layout.total_columns = number.end;
+            layout.numbers.push(number);
+        }
+        for dfa in &public_inputs.dfas {
+            let regex = RegexLayout::new(layout.total_columns, dfa.clone());
+            layout.total_columns = regex.end;
+            layout.regexes.push(regex);
+        }
+        for nth in &public_inputs.nth_occurrences {
+            let occurrence = OccurrenceLayout::new(layout.total_columns, config.max_substring_len);
+            layout.total_columns = occurrence.end;
+            layout.nth_occurrences.push(NthOccurrenceLayout {
+                window: nth.window,
+                occurrence,
+            });
+        }
+        for _ in &public_inputs.counts {
+            let count = CountLayout::new(layout.total_columns, config.max_substring_len);
+            layout.total_columns = count.end;
+            layout.counts.push(count);
+        }
+        for normalized in &public_inputs.normalized {
+            let normalizer = NormalizerLayout::new(
+                layout.total_columns,
+                normalized.window,
+                normalized.normalization,
+            );
+            layout.total_columns = normalizer.end;
+            layout.normalizers.push(normalizer);
+        }
+        for document in &public_inputs.redacted {
+            let redaction = RedactionLayout::new(
+                layout.total_columns,
+                redaction::boundaries(&document.hidden).len(),
+            );
+            layout.total_columns = redaction.end;
+            layout.redactions.push(redaction);
+        }
+        layout.masked = public_inputs
+            .masked
+            .iter()
+            .map(|masked| masked.window)
+            .collect();
+        layout.prefixes = public_inputs.prefixes.clone();
+        layout.suffixes = public_inputs.suffixes.clone();
+        layout.orders = public_inputs.orders.clone();
+        layout.positions = public_inputs
+            .positions
+            .iter()
+            .map(|position| position.window)
+            .collect();
+        layout
+    }
+}
+
+impl Default for TraceLayout {
+    fn default() -> Self {
+        Self::new(&CircuitConfig::default())
+    }
 }
 
 /// 计算轨迹生成器
@@ -49,30 +215,27 @@ pub struct TraceGenerator {
 
 impl TraceGenerator {
     pub fn new(config: CircuitConfig) -> Self {
-        Self {
-            layout: TraceLayout::default(),
-            config,
-        }
+        Self::with_layout(TraceLayout::new(&config), config)
+    }
+
+    /// 使用给定的列布局（例如多个匹配窗口）
+    pub fn with_layout(layout: TraceLayout, config: CircuitConfig) -> Self {
+        Self { layout, config }
     }
 
     /// 计算所需的 trace 长度
-    pub fn compute_trace_length(&self, params: &CircuitParams) -> usize {
-        let witness = params.witness.as_ref().expect("Witness required for trace generation");
-        
-        // SHA-256 需要的步数：对于单块，需要64轮
-        let sha_steps = if self.config.enable_multi_block_sha {
-            // 多块支持：每512位一块，每块64轮
-            let blocks = (witness.plaintext.len() + 63) / 64; // 向上取整
-            blocks * 64
-        } else {
-            64 // 单块固定64轮
-        };
+    ///
+    /// 长度只取决于电路配置而不取决于原文，避免泄露原文长度；
+    /// STARK 要求行数为2的幂
+    pub fn compute_trace_length(&self, _params: &CircuitParams) -> usize {
+        // SHA-256 需要的行数：每块64轮，外加一行摘要行
+        let sha_rows = self.config.max_sha_blocks() * ROUNDS + 1;
 
-        // 字节处理步数：原文长度
-        let byte_steps = witness.plaintext.len();
+        // 字节处理步数：原文最大长度
+        let byte_steps = self.config.max_text_len;
 
         // 取最大值，确保所有约束都能得到验证
-        sha_steps.max(byte_steps).max(64) // 最小64步
+        sha_rows.max(byte_steps).next_power_of_two()
     }
 
     /// 生成完整的计算轨迹
@@ -84,7 +247,7 @@ impl TraceGenerator {
         let mut trace = vec![vec![F::ZERO; trace_length]; self.layout.total_columns];
 
         // 1. 生成 SHA-256 轨迹
-        self.generate_sha256_trace(&mut trace, witness, trace_length)?;
+        self.generate_sha256_trace(&mut trace, witness)?;
 
         // 2. 生成字节处理轨迹
         self.generate_byte_trace(&mut trace, params, trace_length)?;
@@ -93,43 +256,66 @@ impl TraceGenerator {
         self.generate_substring_trace(&mut trace, params, trace_length)?;
 
         // 4. 生成范围检查轨迹
-        self.generate_range_check_trace(&mut trace, witness, trace_length)?;
+        self.generate_range_check_trace(&mut trace, witness)?;
+
+        // 5. 生成子串不出现轨迹
+        for (absence, absent) in self.layout.absent.iter().zip(&params.public_inputs.absent) {
+            absence.generate_trace(&mut trace, &witness.plaintext, &absent.bytes)?;
+        }
+
+        // 6. 生成数值区间轨迹
+        self.generate_number_trace(&mut trace, params)?;
+
+        // 7. 生成正则匹配轨迹
+        for regex in &self.layout.regexes {
+            regex.generate_trace(&mut trace, &witness.plaintext)?;
+        }
+
+        // 8. 生成第 n 次出现轨迹
+        for nth in &self.layout.nth_occurrences {
+            let window = params.public_inputs.windows.get(nth.window)
+                .ok_or(ZkpError::InvalidPublicInput("Occurrence window out of bounds".to_string()))?;
+            nth.occurrence.generate_trace(&mut trace, &witness.plaintext, &window.bytes)?;
+        }
+
+        // 9. 生成出现次数轨迹
+        for (count, counted) in self.layout.counts.iter().zip(&params.public_inputs.counts) {
+            count.generate_trace(&mut trace, &witness.plaintext, &counted.bytes, counted.min, counted.max)?;
+        }
+
+        // 10. 生成规范化轨迹
+        for normalizer in &self.layout.normalizers {
+            normalizer.generate_trace(&mut trace, &witness.plaintext);
+        }
+
+        // 11. 生成涂黑文档轨迹
+        for (redaction, document) in self.layout.redactions.iter().zip(&params.public_inputs.redacted) {
+            redaction.generate_trace(&mut trace, &witness.plaintext, &params.public_inputs.commitment, document);
+        }
 
         Ok(trace)
     }
 
     /// 生成 SHA-256 相关的 trace 列
-    fn generate_sha256_trace<F: Field + QuotientMap<u8>>(
+    ///
+    /// 逐块逐轮记录压缩函数的工作变量、消息调度窗口、进位与填充后的消息字节，
+    /// 最后一行的链接值即为原文摘要
+    fn generate_sha256_trace<F: Field>(
         &self,
         trace: &mut [Vec<F>],
         witness: &crate::config::CircuitWitness,
-        trace_length: usize,
     ) -> Result<(), ZkpError> {
-        // TODO: 使用 p3-sha256 实现完整的 SHA-256 轨迹生成
-        // 这里做简化实现，实际应该包含：
-        // - 消息预处理和 padding
-        // - 64轮的消息调度 (W[t] = ...)
-        // - 8个状态寄存器的更新 (a, b, c, d, e, f, g, h)
-        // - 每轮的压缩函数计算
-
-        use sha2::{Digest, Sha256};
-        
-        // 计算最终哈希作为验证（实际电路中需要逐步计算）
-        let mut hasher = Sha256::new();
-        hasher.update(&witness.plaintext);
-        let hash = hasher.finalize();
-
-        // 在最后几行存储哈希输出用于约束检查
-        if trace_length >= 32 {
-            for (i, &byte) in hash.iter().enumerate() {
-                if i < self.layout.sha_state_cols.len() && trace_length > 32 {
-                    trace[self.layout.sha_state_cols.start + i][trace_length - 32 + i] = 
-                        F::from_int(byte);
-                }
-            }
+        let blocks = sha256::num_blocks(witness.plaintext.len());
+        if blocks > self.config.max_sha_blocks() {
+            return Err(ZkpError::InvalidWitness(format!(
+                "Plaintext needs {} SHA-256 blocks, config allows {} (enable_multi_block_sha: {})",
+                blocks,
+                self.config.max_sha_blocks(),
+                self.config.enable_multi_block_sha
+            )));
         }
 
-        Ok(())
+        self.layout.sha256.generate_trace(trace, &witness.plaintext)
     }
 
     /// 生成字节处理轨迹
@@ -141,7 +327,8 @@ impl TraceGenerator {
     ) -> Result<(), ZkpError> {
         let witness = params.witness.as_ref().unwrap();
 
-        // 填充原文字节到 trace
+        // 填充原文字节到 trace：第 i 行即填充后消息的第 i 个字节，
+        // 与 SHA-256 子电路的消息字节流逐行对齐
         for (i, &byte) in witness.plaintext.iter().enumerate() {
             if i < trace_length {
                 trace[self.layout.plaintext_col][i] = F::from_int(byte);
@@ -159,43 +346,161 @@ impl TraceGenerator {
         trace_length: usize,
     ) -> Result<(), ZkpError> {
         let witness = params.witness.as_ref().unwrap();
-        let substring = &params.public_inputs.substring;
-        let offset = witness.offset;
-
-        // 标记匹配窗口和填充子串字节
-        for (i, &byte) in substring.iter().enumerate() {
-            let pos = offset + i;
-            if pos < trace_length {
-                // 填充子串字节
-                trace[self.layout.substring_col][pos] = F::from_int(byte);
-                
-                // 设置偏移指示器（标记这是匹配窗口）
-                trace[self.layout.offset_indicator_col][pos] = F::ONE;
-                
-                // 设置匹配标志（如果字节匹配）
-                if pos < witness.plaintext.len() && witness.plaintext[pos] == byte {
-                    trace[self.layout.match_flag_col][pos] = F::ONE;
+        let windows = &params.public_inputs.windows;
+        if windows.len() != self.layout.windows.len() || witness.offsets.len() != windows.len() {
+            return Err(ZkpError::InvalidWitness(format!(
+                "Expected {} windows, got {} public windows and {} offsets",
+                self.layout.windows.len(),
+                windows.len(),
+                witness.offsets.len()
+            )));
+        }
+
+        let windows = self.layout.windows.iter().zip(windows).zip(&witness.offsets);
+        for (w, ((layout, window), &offset)) in windows.enumerate() {
+            if let Some(normalizer) = self.layout.normalizers.iter().find(|n| n.window == w) {
+                self.generate_normalized_window_trace(
+                    trace,
+                    layout,
+                    normalizer,
+                    &witness.plaintext,
+                    &window.bytes,
+                    offset,
+                )?;
+                continue;
+            }
+
+            // 被隐藏的字节不与原文比较
+            let hidden = params
+                .public_inputs
+                .masked
+                .iter()
+                .find(|masked| masked.window == w)
+                .map(|masked| &masked.hidden[..])
+                .unwrap_or_default();
+
+            // 标记匹配窗口和填充子串字节
+            for (i, &byte) in window.bytes.iter().enumerate() {
+                let pos = offset + i;
+                if pos < trace_length {
+                    // 填充子串字节
+                    trace[layout.substring_col][pos] = F::from_int(byte);
+
+                    // 设置偏移指示器（标记这是匹配窗口）
+                    trace[layout.offset_indicator_col][pos] = F::ONE;
+
+                    // 设置子串下标选择子（本行对应子串的第 i 个字节）
+                    trace[layout.substring_sel_cols.start + i][pos] = F::ONE;
+
+                    // 设置匹配标志（如果字节匹配或被隐藏）
+                    let hidden = hidden.get(i).copied().unwrap_or(false);
+                    if pos < witness.plaintext.len() && (hidden || witness.plaintext[pos] == byte) {
+                        trace[layout.match_flag_col][pos] = F::ONE;
+                    }
                 }
             }
+
+            // 窗口计数：偏移指示器的前缀和
+            trace[layout.window_count_col] = trace[layout.offset_indicator_col]
+                .iter()
+                .scan(F::ZERO, |count, &indicator| {
+                    *count += indicator;
+                    Some(*count)
+                })
+                .collect();
         }
 
         Ok(())
     }
 
+    /// 生成与规范化原文比较的窗口轨迹
+    ///
+    /// 窗口的第 j 个字节对应规范化流中的一个字节，其来源字符的所有原文行
+    /// （三字节序列为3行）都标记为窗口的第 j 个字节，窗口计数只在规则起点行上累加
+    fn generate_normalized_window_trace<F: Field + QuotientMap<u8>>(
+        &self,
+        trace: &mut [Vec<F>],
+        layout: &WindowLayout,
+        normalizer: &NormalizerLayout,
+        plaintext: &[u8],
+        bytes: &[u8],
+        offset: usize,
+    ) -> Result<(), ZkpError> {
+        let stream = normalize(plaintext, &normalizer.rules);
+        let start = stream
+            .iter()
+            .position(|normalized| normalized.offset == offset)
+            .ok_or(ZkpError::InvalidWitness(
+                "Window does not start at a normalized character".to_string(),
+            ))?;
+
+        let mut counted = vec![F::ZERO; trace[0].len()];
+        for (i, &byte) in bytes.iter().enumerate() {
+            let normalized = stream.get(start + i).ok_or(ZkpError::InvalidWitness(
+                "Normalized window runs past the plaintext".to_string(),
+            ))?;
+            let width = normalizer.rules[normalized.rule].width();
+            let rows = normalized.offset .. normalized.offset + width;
+            trace[layout.substring_col][rows.clone()].fill(F::from_int(byte));
+            trace[layout.offset_indicator_col][rows.clone()].fill(F::ONE);
+            trace[layout.substring_sel_cols.start + i][rows.clone()].fill(F::ONE);
+            if normalized.byte == byte {
+                trace[layout.match_flag_col][rows].fill(F::ONE);
+            }
+            counted[normalized.offset] = F::ONE;
+        }
+
+        trace[layout.window_count_col] = counted
+            .iter()
+            .scan(F::ZERO, |count, &indicator| {
+                *count += indicator;
+                Some(*count)
+            })
+            .collect();
+
+        Ok(())
+    }
+
     /// 生成范围检查轨迹
-    fn generate_range_check_trace<F: Field + QuotientMap<u8>>(
+    ///
+    /// 对填充后消息的每个字节写入小端8位分解，未使用的行保持为零
+    fn generate_range_check_trace<F: Field>(
         &self,
         trace: &mut [Vec<F>],
         witness: &crate::config::CircuitWitness,
-        trace_length: usize,
     ) -> Result<(), ZkpError> {
-        // 为每个字节设置范围检查标志（确保在[0,255]范围内）
-        for (i, _byte) in witness.plaintext.iter().enumerate() {
-            if i < trace_length {
-                // 简单的范围检查：如果字节值合法则设为1
-                // u8 类型的字节总是在 [0, 255] 范围内
-                trace[self.layout.range_check_col][i] = F::ONE;
-            }
+        for (row, &byte) in sha256::pad(&witness.plaintext)
+            .iter()
+            .flatten()
+            .enumerate()
+        {
+            write_bits(trace, &self.layout.range_bits_cols, row, u64::from(byte));
+        }
+
+        Ok(())
+    }
+
+    /// 生成数值区间轨迹
+    ///
+    /// 每个数字段从其标签窗口之后的一行开始
+    fn generate_number_trace<F: Field>(
+        &self,
+        trace: &mut [Vec<F>],
+        params: &CircuitParams,
+    ) -> Result<(), ZkpError> {
+        let witness = params.witness.as_ref().unwrap();
+        let message: Vec<u8> = sha256::pad(&witness.plaintext).concat();
+
+        for (number, range) in self.layout.numbers.iter().zip(&params.public_inputs.ranges) {
+            let start = witness.offsets[range.window]
+                + params.public_inputs.windows[range.window].bytes.len();
+            number.generate_trace(
+                trace,
+                &message,
+                witness.plaintext.len(),
+                start,
+                (range.min, range.max),
+            )?;
         }
 
         Ok(())
@@ -206,6 +511,21 @@ impl TraceGenerator {
     }
 }
 
+/// 将按列存储的 trace 转换为 Plonky3 使用的行主序矩阵
+pub fn to_row_major<F: Field>(trace: &[Vec<F>]) -> RowMajorMatrix<F> {
+    let width = trace.len();
+    let height = trace[0].len();
+
+    let mut values = vec![F::ZERO; width * height];
+    for (col, column) in trace.iter().enumerate() {
+        for (row, &value) in column.iter().enumerate() {
+            values[row * width + col] = value;
+        }
+    }
+
+    RowMajorMatrix::new(values, width)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -228,25 +548,57 @@ mod tests {
 
         let params = CircuitParams {
             config,
-            public_inputs: PublicInputs {
-                commitment,
-                substring,
-            },
+            public_inputs: PublicInputs::substring(commitment, substring),
             witness: Some(CircuitWitness {
                 plaintext,
-                offset: 0,
+                offsets: vec![0],
             }),
         };
 
         let trace = generator.generate_trace::<Goldilocks>(&params).unwrap();
         
+        let layout = generator.get_layout();
+        let window = &layout.windows[0];
+
         // 验证 trace 的基本属性
-        assert_eq!(trace.len(), 101); // 总列数
+        assert_eq!(trace.len(), layout.total_columns); // 总列数
         assert!(trace[0].len() >= 64); // 至少64行
+        assert!(trace[0].len().is_power_of_two()); // 行数为2的幂
 
         // 验证字节数据被正确填充
-        assert_eq!(trace[96][0], Goldilocks::from_int(b'h')); // 第一个字符
-        assert_eq!(trace[99][0], Goldilocks::ONE); // 偏移指示器
-        assert_eq!(trace[98][0], Goldilocks::ONE); // 匹配标志
+        assert_eq!(trace[layout.plaintext_col][0], Goldilocks::from_int(b'h')); // 第一个字符
+        assert_eq!(trace[window.offset_indicator_col][0], Goldilocks::ONE); // 偏移指示器
+        assert_eq!(trace[window.match_flag_col][0], Goldilocks::ONE); // 匹配标志
+        assert_eq!(
+            trace[window.window_count_col][trace[0].len() - 1],
+            Goldilocks::from_int(5u8)
+        ); // 窗口大小
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_trace_length_scales_with_block_count() {
+        let params = |config: CircuitConfig| CircuitParams {
+            config,
+            public_inputs: PublicInputs::substring([0; 32], Vec::new()),
+            witness: None,
+        };
+
+        // 单块：64轮 + 摘要行
+        let config = CircuitConfig::default();
+        let generator = TraceGenerator::new(config.clone());
+        assert_eq!(generator.compute_trace_length(&params(config)), 128);
+
+        // 64 KiB 原文填充后为1025块
+        let config = CircuitConfig {
+            max_text_len: 64 * 1024,
+            max_substring_len: 32,
+            enable_multi_block_sha: true,
+        };
+        assert_eq!(config.max_sha_blocks(), 1025);
+        let generator = TraceGenerator::new(config.clone());
+        assert_eq!(
+            generator.compute_trace_length(&params(config)),
+            (1025 * 64 + 1usize).next_power_of_two()
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::circuits::dfa::Dfa;
+use crate::circuits::normalize::Normalization;
+use crate::hash::sha256;
+
 /// 电路配置参数
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CircuitConfig {
@@ -22,13 +26,171 @@ impl Default for CircuitConfig {
     }
 }
 
+impl CircuitConfig {
+    /// SHA-256 压缩的最大块数
+    ///
+    /// 单块模式固定为1（原文不超过55字节）；多块模式按 `max_text_len` 填充后的块数计算，
+    /// 例如 64 KiB 原文需要1025块
+    pub fn max_sha_blocks(&self) -> usize {
+        if self.enable_multi_block_sha {
+            sha256::num_blocks(self.max_text_len)
+        } else {
+            1
+        }
+    }
+}
+
+/// 一个匹配窗口的公开输入：原文中某个私有偏移处出现这段字节
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowInput {
+    /// 窗口内的公开字节串
+    pub bytes: Vec<u8>,
+}
+
+impl WindowInput {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+}
+
 /// 电路公开输入参数
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PublicInputs {
     /// 原文的 SHA-256 承诺（32字节）
     pub commitment: [u8; 32],
-    /// 要证明的公开子串
-    pub substring: Vec<u8>,
+    /// 匹配窗口，各自有独立的私有偏移；子串命题只有一个窗口，锚定子串命题为 `[anchor, target]`，
+    /// 组合命题依次拼接各子命题的窗口
+    pub windows: Vec<WindowInput>,
+    /// 公开的原文长度（可选）
+    pub length: Option<usize>,
+    /// 数值区间：各自以某个窗口为标签，紧随其后的十进制数落在区间内
+    pub ranges: Vec<ValueRange>,
+    /// 正则命题的 DFA，由公开模式编译得到
+    pub dfas: Vec<Dfa>,
+    /// 证明不出现在原文中的字节串
+    pub absent: Vec<WindowInput>,
+    /// 窗口之间的先后顺序（有序子串命题）
+    pub orders: Vec<WindowOrder>,
+    /// 起点公开的窗口（公开偏移子串命题）
+    pub positions: Vec<WindowPosition>,
+    /// 起点为其子串第 n 次出现的窗口（第 n 次出现命题）
+    pub nth_occurrences: Vec<NthOccurrence>,
+    /// 出现次数落在公开区间内的字节串（出现次数命题）
+    pub counts: Vec<OccurrenceCount>,
+    /// 从原文第一个字节开始的窗口下标（前缀命题）
+    pub prefixes: Vec<usize>,
+    /// 在原文最后一个字节结束的窗口下标（后缀命题）
+    pub suffixes: Vec<usize>,
+    /// 与规范化后的原文比较的窗口（规范化子串命题）
+    pub normalized: Vec<NormalizedWindow>,
+    /// 部分字节被隐藏、只比较其余字节的窗口（掩码子串命题）
+    pub masked: Vec<MaskedWindow>,
+    /// 与原文逐字节比较的涂黑文档（涂黑文档命题）
+    pub redacted: Vec<RedactedDocument>,
+}
+
+/// 数值区间 `[min, max]`（闭区间）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValueRange {
+    /// 标签所在窗口在 [`PublicInputs::windows`] 中的下标
+    pub window: usize,
+    pub min: u64,
+    pub max: u64,
+}
+
+/// 两个窗口的先后顺序：`before` 窗口结束之后 `after` 窗口才开始，
+/// 即 `offset_before + |before| ≤ offset_after`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowOrder {
+    /// 在前的窗口在 [`PublicInputs::windows`] 中的下标
+    pub before: usize,
+    /// 在后的窗口的下标
+    pub after: usize,
+}
+
+/// 公开的窗口起点：第 `window` 个窗口从原文第 `offset` 个字节开始
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowPosition {
+    /// 窗口在 [`PublicInputs::windows`] 中的下标
+    pub window: usize,
+    /// 公开的偏移（从0开始的字节下标）
+    pub offset: usize,
+}
+
+/// 第 `window` 个窗口的起点是其子串在原文中的第 `n` 次出现（从1开始，重叠的出现分别计数）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NthOccurrence {
+    /// 窗口在 [`PublicInputs::windows`] 中的下标
+    pub window: usize,
+    /// 公开的出现序号
+    pub n: usize,
+}
+
+/// 字节串 `bytes` 在原文中的出现次数落在 `[min, max]` 内（重叠的出现分别计数）
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OccurrenceCount {
+    /// 被计数的公开字节串
+    pub bytes: Vec<u8>,
+    pub min: usize,
+    pub max: usize,
+}
+
+/// 第 `window` 个窗口的字节串是规范化后的子串，与按同一规则规范化后的原文逐字节比较
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizedWindow {
+    /// 窗口在 [`PublicInputs::windows`] 中的下标
+    pub window: usize,
+    /// 公开的规范化规则
+    pub normalization: Normalization,
+}
+
+/// 第 `window` 个窗口中 `hidden[j]` 为真的字节被隐藏：不与原文比较，公开值中记为0
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaskedWindow {
+    /// 窗口在 [`PublicInputs::windows`] 中的下标
+    pub window: usize,
+    /// 每个窗口字节是否被隐藏（与窗口字节串等长）
+    pub hidden: Vec<bool>,
+}
+
+/// 涂黑文档：与原文等长，`hidden[k]` 为假的字节与原文第 k 个字节相等
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactedDocument {
+    /// 文档字节，被涂黑的字节记为0
+    pub bytes: Vec<u8>,
+    /// 每个字节是否被涂黑（与文档等长）
+    pub hidden: Vec<bool>,
+}
+
+impl PublicInputs {
+    /// 只有承诺、没有任何命题的公开输入
+    pub fn new(commitment: [u8; 32]) -> Self {
+        Self {
+            commitment,
+            windows: Vec::new(),
+            length: None,
+            ranges: Vec::new(),
+            dfas: Vec::new(),
+            absent: Vec::new(),
+            orders: Vec::new(),
+            positions: Vec::new(),
+            nth_occurrences: Vec::new(),
+            counts: Vec::new(),
+            prefixes: Vec::new(),
+            suffixes: Vec::new(),
+            normalized: Vec::new(),
+            masked: Vec::new(),
+            redacted: Vec::new(),
+        }
+    }
+
+    /// 单个子串窗口的公开输入
+    pub fn substring(commitment: [u8; 32], substring: Vec<u8>) -> Self {
+        Self {
+            windows: vec![WindowInput::new(substring)],
+            ..Self::new(commitment)
+        }
+    }
 }
 
 /// 电路私有输入参数（见证）
@@ -36,8 +198,8 @@ pub struct PublicInputs {
 pub struct CircuitWitness {
     /// 完整的原文（UTF-8字节）
     pub plaintext: Vec<u8>,
-    /// 子串在原文中的偏移位置
-    pub offset: usize,
+    /// 各窗口在原文中的偏移位置，与 [`PublicInputs::windows`] 一一对应
+    pub offsets: Vec<usize>,
 }
 
 /// 电路参数集合
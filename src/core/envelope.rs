@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::types::{Bytes, Claim},
+    error::ZkpError,
+};
+
+/// 证明信封的魔数，用于识别本库产生的证明
+pub const PROOF_MAGIC: [u8; 4] = *b"ZKPR";
+
+/// 当前证明格式版本
+///
+/// 头部（魔数 + 版本）固定为 `magic(4) || version(u16 小端)`，不随版本变化，
+/// 因此旧版本的证明总能被解析出版本号并以明确的错误拒绝
+pub const PROOF_FORMAT_VERSION: u16 = 1;
+
+/// 生成证明的后端
+///
+/// 以变体序号编码，新增后端只能追加在末尾
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackendId {
+    /// 模拟后端（仅用于测试）
+    Mock,
+    /// Plonky3 uni-stark 后端
+    Plonky3UniStark,
+    /// Plonky3 uni-stark 后端（零知识隐藏模式）
+    Plonky3UniStarkHiding,
+}
+
+/// 证明所在的有限域
+///
+/// 以变体序号编码，新增域只能追加在末尾
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldId {
+    /// 不依赖有限域的后端
+    None,
+    /// Goldilocks 域 p = 2^64 - 2^32 + 1
+    Goldilocks,
+}
+
+/// 证明所针对的命题种类
+///
+/// 以变体序号编码，新增命题只能追加在末尾
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClaimKind {
+    /// 子串包含
+    Substring,
+    /// 锚定子串
+    AnchoredSubstring,
+    /// 数值区间
+    Range,
+    /// 正则匹配
+    Regex,
+    /// 子串不出现
+    NotContains,
+    /// 组合命题
+    All,
+    /// 有序子串
+    Ordered,
+    /// 公开偏移子串
+    SubstringAt,
+    /// 第 n 次出现
+    NthOccurrence,
+    /// 出现次数
+    Count,
+    /// 前缀
+    Prefix,
+    /// 后缀
+    Suffix,
+    /// 规范化子串
+    NormalizedSubstring,
+    /// 掩码子串
+    MaskedSubstring,
+    /// 涂黑文档
+    Redacted,
+    /// 分块承诺上的子串
+    ChunkedSubstring,
+}
+
+impl From<&Claim> for ClaimKind {
+    fn from(claim: &Claim) -> Self {
+        match claim {
+            Claim::Substring { .. } => ClaimKind::Substring,
+            Claim::AnchoredSubstring { .. } => ClaimKind::AnchoredSubstring,
+            Claim::Range { .. } => ClaimKind::Range,
+            Claim::Regex { .. } => ClaimKind::Regex,
+            Claim::NotContains { .. } => ClaimKind::NotContains,
+            Claim::All(_) => ClaimKind::All,
+            Claim::Ordered { .. } => ClaimKind::Ordered,
+            Claim::SubstringAt { .. } => ClaimKind::SubstringAt,
+            Claim::NthOccurrence { .. } => ClaimKind::NthOccurrence,
+            Claim::Count { .. } => ClaimKind::Count,
+            Claim::Prefix { .. } => ClaimKind::Prefix,
+            Claim::Suffix { .. } => ClaimKind::Suffix,
+            Claim::NormalizedSubstring { .. } => ClaimKind::NormalizedSubstring,
+            Claim::MaskedSubstring { .. } => ClaimKind::MaskedSubstring,
+            Claim::Redacted { .. } => ClaimKind::Redacted,
+            Claim::ChunkedSubstring { .. } => ClaimKind::ChunkedSubstring,
+        }
+    }
+}
+
+/// 带版本的自描述证明信封
+///
+/// 编码格式：`magic || version || bincode(backend, field, claim, config_hash, payload)`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofEnvelope {
+    /// 证明格式版本
+    pub version: u16,
+    /// 生成证明的后端
+    pub backend: BackendId,
+    /// 证明所在的有限域
+    pub field: FieldId,
+    /// 命题种类
+    pub claim: ClaimKind,
+    /// 电路配置与后端参数的哈希，验证方必须使用相同的配置
+    pub config_hash: [u8; 32],
+    /// 后端自身的证明字节
+    pub payload: Bytes,
+}
+
+type EnvelopeBody = (BackendId, FieldId, ClaimKind, [u8; 32], Bytes);
+
+impl ProofEnvelope {
+    /// 以当前格式版本创建信封
+    pub fn new(
+        backend: BackendId,
+        field: FieldId,
+        claim: ClaimKind,
+        config_hash: [u8; 32],
+        payload: Bytes,
+    ) -> Self {
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            backend,
+            field,
+            claim,
+            config_hash,
+            payload,
+        }
+    }
+
+    /// 编码为字节
+    pub fn to_bytes(&self) -> Result<Bytes, ZkpError> {
+        let body: EnvelopeBody = (
+            self.backend,
+            self.field,
+            self.claim,
+            self.config_hash,
+            self.payload.clone(),
+        );
+        let encoded =
+            bincode::serde::encode_to_vec(&body, bincode::config::standard()).map_err(|e| {
+                ZkpError::SerializationError(format!("Envelope encoding failed: {}", e))
+            })?;
+
+        let mut bytes = Vec::with_capacity(PROOF_MAGIC.len() + 2 + encoded.len());
+        bytes.extend_from_slice(&PROOF_MAGIC);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&encoded);
+        Ok(bytes)
+    }
+
+    /// 从字节解码，魔数或版本不符时返回 [`ZkpError::UnsupportedProofFormat`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ZkpError> {
+        let header_len = PROOF_MAGIC.len() + 2;
+        if bytes.len() < header_len || bytes[.. PROOF_MAGIC.len()] != PROOF_MAGIC {
+            return Err(ZkpError::UnsupportedProofFormat(
+                "Missing proof envelope magic".to_string(),
+            ));
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != PROOF_FORMAT_VERSION {
+            return Err(ZkpError::UnsupportedProofFormat(format!(
+                "Proof format version {} is not supported (expected {})",
+                version, PROOF_FORMAT_VERSION
+            )));
+        }
+
+        let ((backend, field, claim, config_hash, payload), read): (EnvelopeBody, usize) =
+            bincode::serde::decode_from_slice(&bytes[header_len ..], bincode::config::standard())
+                .map_err(|e| {
+                ZkpError::SerializationError(format!("Envelope decoding failed: {}", e))
+            })?;
+        if header_len + read != bytes.len() {
+            return Err(ZkpError::SerializationError(
+                "Trailing bytes after proof envelope".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            version,
+            backend,
+            field,
+            claim,
+            config_hash,
+            payload,
+        })
+    }
+
+    /// 检查信封头部与验证方的期望是否一致
+    pub fn ensure_matches(
+        &self,
+        backend: BackendId,
+        field: FieldId,
+        claim: ClaimKind,
+        config_hash: &[u8; 32],
+    ) -> Result<(), ZkpError> {
+        if self.backend != backend {
+            return Err(ZkpError::UnsupportedProofFormat(format!(
+                "Proof was produced by backend {:?}, expected {:?}",
+                self.backend, backend
+            )));
+        }
+        if self.field != field {
+            return Err(ZkpError::UnsupportedProofFormat(format!(
+                "Proof is over field {:?}, expected {:?}",
+                self.field, field
+            )));
+        }
+        if self.claim != claim {
+            return Err(ZkpError::UnsupportedProofFormat(format!(
+                "Proof is for a {:?} claim, expected {:?}",
+                self.claim, claim
+            )));
+        }
+        if self.config_hash != *config_hash {
+            return Err(ZkpError::UnsupportedProofFormat(
+                "Proof was produced under a different circuit configuration".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope() -> ProofEnvelope {
+        ProofEnvelope::new(
+            BackendId::Plonky3UniStark,
+            FieldId::Goldilocks,
+            ClaimKind::Substring,
+            [7; 32],
+            vec![1, 2, 3],
+        )
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let bytes = envelope().to_bytes().unwrap();
+        assert_eq!(&bytes[.. 4], b"ZKPR");
+        assert_eq!(ProofEnvelope::from_bytes(&bytes).unwrap(), envelope());
+    }
+
+    #[test]
+    fn test_envelope_rejects_unknown_format() {
+        let bytes = envelope().to_bytes().unwrap();
+
+        // 魔数错误
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 1;
+        assert!(matches!(
+            ProofEnvelope::from_bytes(&bad_magic),
+            Err(ZkpError::UnsupportedProofFormat(_))
+        ));
+
+        // 未来版本
+        let mut future = bytes.clone();
+        future[4 .. 6].copy_from_slice(&(PROOF_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            ProofEnvelope::from_bytes(&future),
+            Err(ZkpError::UnsupportedProofFormat(msg)) if msg.contains("version")
+        ));
+
+        // 截断与多余字节
+        assert!(ProofEnvelope::from_bytes(&bytes[.. bytes.len() - 1]).is_err());
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(ProofEnvelope::from_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_envelope_header_mismatch() {
+        let envelope = envelope();
+        assert!(
+            envelope
+                .ensure_matches(
+                    BackendId::Plonky3UniStark,
+                    FieldId::Goldilocks,
+                    ClaimKind::Substring,
+                    &[7; 32]
+                )
+                .is_ok()
+        );
+        assert!(
+            envelope
+                .ensure_matches(
+                    BackendId::Mock,
+                    FieldId::Goldilocks,
+                    ClaimKind::Substring,
+                    &[7; 32]
+                )
+                .is_err()
+        );
+        assert!(
+            envelope
+                .ensure_matches(
+                    BackendId::Plonky3UniStark,
+                    FieldId::Goldilocks,
+                    ClaimKind::Substring,
+                    &[8; 32]
+                )
+                .is_err()
+        );
+    }
+}
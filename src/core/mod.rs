@@ -1,5 +1,7 @@
+pub mod envelope;
 pub mod traits;
 pub mod types;
 
+pub use envelope::*;
 pub use traits::*;
 pub use types::*;
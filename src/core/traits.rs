@@ -1,10 +1,16 @@
-use crate::core::types::{Commitment, Proof, Statement, Witness};
+use crate::{
+    core::types::{Commitment, Proof, Statement, Witness},
+    error::ZkpError,
+};
 
 pub trait Commit {
-    fn commit(&self, input: &[u8]) -> Commitment;
+    fn commit(&self, input: &[u8]) -> Result<Commitment, ZkpError>;
 }
 
 pub trait Prove {
-    fn prove(&self, statement: &Statement, witness: &Witness) -> Proof;
-    fn verify(&self, statement: &Statement, proof: &Proof) -> bool;
+    /// 生成证明；见证不满足命题或证明生成失败时返回错误，而不是返回一个无效的证明
+    fn prove(&self, statement: &Statement, witness: &Witness) -> Result<Proof, ZkpError>;
+
+    /// 验证证明；拒绝时返回具体原因
+    fn verify(&self, statement: &Statement, proof: &Proof) -> Result<(), ZkpError>;
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::circuits::normalize::Normalization;
+
 /// 原始字节封装
 pub type Bytes = Vec<u8>;
 
@@ -27,13 +29,70 @@ pub struct Statement {
 pub enum Claim {
     /// 证明原文包含某个子串
     Substring { value: String },
-    // 以后可以扩展更多，比如:
-    // Range { min: i64, max: i64 },
-    // Regex { pattern: String },
+    /// 证明目标子串与公开的锚文本出现在同一份原文中，两者的偏移都保持私密；
+    /// 可选公开原文长度
+    AnchoredSubstring {
+        anchor: String,
+        target: String,
+        length: Option<usize>,
+    },
+    /// 证明原文中紧跟在公开标签之后的十进制数落在 `[min, max]` 内，数值本身保持私密
+    Range { label: String, min: u64, max: u64 },
+    /// 证明原文匹配公开的正则表达式（未锚定时为包含匹配），匹配位置与内容保持私密
+    Regex { pattern: String },
+    /// 证明原文不包含某个子串
+    NotContains { value: String },
+    /// 同时证明多个命题：SHA-256 只计算一次，各子命题的窗口共享同一份原文，偏移各自私密
+    All(Vec<Claim>),
+    /// 证明若干子串在原文中依次出现且互不重叠：前一个结束之后才开始下一个
+    /// （`offset_a + |a| ≤ offset_b`），偏移保持私密
+    Ordered { values: Vec<String> },
+    /// 证明原文从第 `offset` 个字节起是某个子串，偏移与子串都公开
+    SubstringAt { value: String, offset: usize },
+    /// 证明原文包含某个子串的第 `n` 次出现（从1开始，重叠的出现分别计数），
+    /// 序号公开、偏移保持私密
+    NthOccurrence { value: String, n: usize },
+    /// 证明某个子串在原文中的出现次数落在 `[min, max]` 内（`min == max` 即恰好出现 `min` 次），
+    /// 重叠的出现分别计数
+    Count {
+        value: String,
+        min: usize,
+        max: usize,
+    },
+    /// 证明原文以某个子串开头，可选公开原文长度
+    Prefix {
+        value: String,
+        length: Option<usize>,
+    },
+    /// 证明原文以某个子串结尾，可选公开原文长度
+    Suffix {
+        value: String,
+        length: Option<usize>,
+    },
+    /// 证明按公开规则规范化（ASCII 大小写折叠、全角转半角）后的原文包含同样规范化后的子串，
+    /// 偏移保持私密
+    NormalizedSubstring {
+        value: String,
+        normalization: Normalization,
+    },
+    /// 证明原文包含一个部分字节被隐藏的子串：`hidden[j]` 为真的字节可以是任意值且不公开
+    /// （例如 `ID: ????-1234`，`value` 在这些位置上只放占位符），其余字节逐字节相等，偏移保持私密
+    MaskedSubstring { value: String, hidden: Vec<bool> },
+    /// 证明公开的涂黑文档由承诺的原文得到：两者等长，
+    /// `hidden[j]` 为假的每个字节与原文同一位置的字节相等，被涂黑的字节不公开
+    /// （`document` 在这些位置上的值被忽略）。掩码作用于整份原文，
+    /// 涂黑区间的边界数不能超过 [`crate::circuits::redaction::MAX_REDACTION_BOUNDARIES`]
+    Redacted { document: Bytes, hidden: Vec<bool> },
+    /// 证明按块 Merkle 承诺（`MerkleCommit`）的原文包含某个子串：
+    /// 只对子串所在的块做证明并给出到根的 Merkle 路径，证明开销与原文长度无关。
+    /// 块下标与子串在各块中的长度公开
+    ChunkedSubstring { value: String },
 }
 
 /// 私密见证（证明者持有）
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Witness {
     pub plaintext: Bytes,
+    /// 承诺所用的私有盐（分块 Merkle 承诺由它为每个块派生盐，其他承诺不使用，留空即可）
+    pub salt: Bytes,
 }
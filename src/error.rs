@@ -31,6 +31,10 @@ pub enum ZkpError {
     #[error("Serialization error: {0}")]
     SerializationError(String),
 
+    /// 证明格式不受支持（魔数、版本或头部与验证方不一致）
+    #[error("Unsupported proof format: {0}")]
+    UnsupportedProofFormat(String),
+
     /// 内部错误
     #[error("Internal error: {0}")]
     InternalError(String),
@@ -1,5 +1,28 @@
 use sha2::{Digest, Sha256};
 
+/// SHA-256 轮常量 K[0..64]
+pub const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 初始链接值 H(0)
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// 消息块大小（字节）
+pub const BLOCK_LEN: usize = 64;
+
+/// 压缩函数轮数
+pub const ROUNDS: usize = 64;
+
 pub fn hash(data: &[u8]) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(data);
@@ -13,3 +36,125 @@ pub fn hash_hex(data: &[u8]) -> String {
 pub fn verify(data: &[u8], expected_hash: &[u8; 32]) -> bool {
     hash(data) == *expected_hash
 }
+
+/// 消息填充：`data || 0x80 || 0x00.. || bitlen(64位大端)`，按64字节分块
+pub fn pad(data: &[u8]) -> Vec<[u8; BLOCK_LEN]> {
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % BLOCK_LEN != BLOCK_LEN - 8 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    padded
+        .chunks_exact(BLOCK_LEN)
+        .map(|chunk| chunk.try_into().expect("chunk is one block"))
+        .collect()
+}
+
+/// 填充后的块数
+pub fn num_blocks(data_len: usize) -> usize {
+    (data_len + 9).div_ceil(BLOCK_LEN)
+}
+
+/// 消息调度 W[0..len]，前16个字来自消息块，其余按递推式扩展
+///
+/// 标准只需要 W[0..64]；电路中的滑动窗口会多算出几个字，因此允许 `len > 64`
+pub fn message_schedule(block: &[u8; BLOCK_LEN], len: usize) -> Vec<u32> {
+    let mut w: Vec<u32> = block
+        .chunks_exact(4)
+        .map(|word| u32::from_be_bytes(word.try_into().expect("4 bytes")))
+        .collect();
+    for t in 16 .. len {
+        let next = small_sigma1(w[t - 2])
+            .wrapping_add(w[t - 7])
+            .wrapping_add(small_sigma0(w[t - 15]))
+            .wrapping_add(w[t - 16]);
+        w.push(next);
+    }
+    w
+}
+
+/// 把32字节摘要拆成8个大端字
+pub fn digest_words(digest: &[u8; 32]) -> [u32; 8] {
+    std::array::from_fn(|i| {
+        u32::from_be_bytes(digest[4 * i .. 4 * i + 4].try_into().expect("4 bytes"))
+    })
+}
+
+/// Σ₀(a) = ROTR²(a) ⊕ ROTR¹³(a) ⊕ ROTR²²(a)
+pub fn big_sigma0(x: u32) -> u32 {
+    x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
+}
+
+/// Σ₁(e) = ROTR⁶(e) ⊕ ROTR¹¹(e) ⊕ ROTR²⁵(e)
+pub fn big_sigma1(x: u32) -> u32 {
+    x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
+}
+
+/// σ₀(w) = ROTR⁷(w) ⊕ ROTR¹⁸(w) ⊕ SHR³(w)
+pub fn small_sigma0(x: u32) -> u32 {
+    x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)
+}
+
+/// σ₁(w) = ROTR¹⁷(w) ⊕ ROTR¹⁹(w) ⊕ SHR¹⁰(w)
+pub fn small_sigma1(x: u32) -> u32 {
+    x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
+}
+
+/// Ch(e, f, g) = (e ∧ f) ⊕ (¬e ∧ g)
+pub fn ch(e: u32, f: u32, g: u32) -> u32 {
+    (e & f) ^ (!e & g)
+}
+
+/// Maj(a, b, c) = (a ∧ b) ⊕ (a ∧ c) ⊕ (b ∧ c)
+pub fn maj(a: u32, b: u32, c: u32) -> u32 {
+    (a & b) ^ (a & c) ^ (b & c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 用本模块的常量与函数逐块压缩，结果应与 sha2 一致
+    fn compress_all(data: &[u8]) -> [u8; 32] {
+        let mut hv = IV;
+        for block in pad(data) {
+            let w = message_schedule(&block, ROUNDS);
+            let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = hv;
+            for t in 0 .. ROUNDS {
+                let t1 = h
+                    .wrapping_add(big_sigma1(e))
+                    .wrapping_add(ch(e, f, g))
+                    .wrapping_add(K[t])
+                    .wrapping_add(w[t]);
+                let t2 = big_sigma0(a).wrapping_add(maj(a, b, c));
+                (h, g, f, e, d, c, b, a) =
+                    (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+            }
+            for (hv, x) in hv.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+                *hv = hv.wrapping_add(x);
+            }
+        }
+
+        let mut digest = [0u8; 32];
+        for (i, word) in hv.iter().enumerate() {
+            digest[4 * i .. 4 * i + 4].copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    #[test]
+    fn test_compression_matches_sha2() {
+        for data in [
+            &b""[..],
+            b"hello world!",
+            &[0x61; 55],
+            &[0x62; 56],
+            &[0x63; 200],
+        ] {
+            assert_eq!(compress_all(data), hash(data));
+            assert_eq!(pad(data).len(), num_blocks(data.len()));
+        }
+    }
+}